@@ -0,0 +1,429 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use ratatui::widgets::ListState;
+
+use crate::Audio;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum BrowseRoot {
+    Artists,
+    Genres,
+    Years,
+    Folders,
+}
+
+impl Display for BrowseRoot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BrowseRoot::Artists => write!(f, "Artists"),
+            BrowseRoot::Genres => write!(f, "Genres"),
+            BrowseRoot::Years => write!(f, "Years"),
+            BrowseRoot::Folders => write!(f, "Folders"),
+        }
+    }
+}
+
+impl BrowseRoot {
+    fn levels(&self) -> &'static [Level] {
+        match self {
+            BrowseRoot::Artists => &[Level::Artist, Level::Album, Level::Track],
+            BrowseRoot::Genres => &[Level::Genre, Level::Artist, Level::Album, Level::Track],
+            BrowseRoot::Years => &[Level::Decade, Level::Year, Level::Album, Level::Track],
+            // Folders nest as deep as the disk does.
+            BrowseRoot::Folders => &[Level::Folder],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Level {
+    Genre,
+    Decade,
+    Year,
+    Artist,
+    Album,
+    Track,
+    Folder,
+}
+
+// Rows listed above the entries of every level.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum BrowseAction {
+    PlayAll,
+    ShuffleGenre,
+    PlayDecade,
+    PlayFolder,
+    PlayRecursively,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Entry {
+    pub label: String,
+    pub detail: String,
+    pub tracks: Vec<usize>,
+    pub is_track: bool,
+}
+
+pub(crate) enum Selection<'a> {
+    Action(BrowseAction),
+    Entry(&'a Entry),
+}
+
+pub(crate) struct Browser {
+    pub root: BrowseRoot,
+    pub library_path: PathBuf,
+    // Entries that were drilled into, with the row they were picked from.
+    pub trail: Vec<(Entry, usize)>,
+    pub entries: Vec<Entry>,
+    pub list_state: ListState,
+}
+
+impl Browser {
+    pub fn open(root: BrowseRoot, tracks: &[Audio], library_path: &Path) -> Self {
+        let mut browser = Browser {
+            root,
+            library_path: library_path.to_path_buf(),
+            trail: Vec::new(),
+            entries: Vec::new(),
+            list_state: ListState::default(),
+        };
+        let everything: Vec<usize> = (0..tracks.len()).collect();
+        browser.entries = browser.children(&everything, tracks);
+        browser.select_first_entry();
+        browser
+    }
+
+    pub fn actions(&self) -> Vec<BrowseAction> {
+        match (self.root, self.trail.is_empty()) {
+            (BrowseRoot::Folders, _) => vec![BrowseAction::PlayFolder, BrowseAction::PlayRecursively],
+            (BrowseRoot::Genres, false) => vec![BrowseAction::ShuffleGenre],
+            (BrowseRoot::Years, false) => vec![BrowseAction::PlayDecade],
+            _ => vec![BrowseAction::PlayAll],
+        }
+    }
+
+    pub fn action_label(&self, action: BrowseAction) -> String {
+        let top = self.trail.first().map(|(entry, _)| entry.label.as_str());
+        match action {
+            BrowseAction::PlayAll => String::from("Play all"),
+            BrowseAction::ShuffleGenre => format!("Shuffle {}", top.unwrap_or_default()),
+            BrowseAction::PlayDecade => format!("Play the {}", top.unwrap_or_default()),
+            BrowseAction::PlayFolder => String::from("Play folder"),
+            BrowseAction::PlayRecursively => String::from("Play folder recursively"),
+        }
+    }
+
+    // The genre and decade actions keep to the entry picked at the root, however deep the
+    // browser has gone since, just like their labels say.
+    pub fn action_scope(&self, action: BrowseAction, tracks: &[Audio]) -> Vec<usize> {
+        let top = self.trail.first().map(|(entry, _)| entry.tracks.clone());
+        match action {
+            BrowseAction::PlayFolder => self.track_entries(),
+            BrowseAction::PlayRecursively => {
+                let mut scope = self.listed_tracks();
+                scope.sort_by(|a, b| tracks[*a].path.cmp(&tracks[*b].path));
+                scope
+            }
+            BrowseAction::ShuffleGenre => top.unwrap_or_else(|| self.listed_tracks()),
+            BrowseAction::PlayDecade => {
+                let mut scope = top.unwrap_or_else(|| self.listed_tracks());
+                scope.sort_by_key(|index| tracks[*index].year);
+                scope
+            }
+            BrowseAction::PlayAll => self.listed_tracks(),
+        }
+    }
+
+    pub fn number_of_rows(&self) -> usize {
+        self.actions().len() + self.entries.len()
+    }
+
+    pub fn selected(&self) -> Option<Selection<'_>> {
+        let row = self.list_state.selected()?;
+        let actions = self.actions();
+        match actions.get(row) {
+            Some(action) => Some(Selection::Action(*action)),
+            None => self.entries.get(row - actions.len()).map(Selection::Entry),
+        }
+    }
+
+    // The tracks listed on this level with the position of the selected one among them.
+    pub fn play_from_selected(&self) -> Option<(Vec<usize>, usize)> {
+        let Some(Selection::Entry(entry)) = self.selected() else {
+            return None;
+        };
+        let index = *entry.tracks.first()?;
+        let scope = self.track_entries();
+        let start = scope.iter().position(|track| *track == index)?;
+        Some((scope, start))
+    }
+
+    pub fn enter(&mut self, tracks: &[Audio]) {
+        let Some(row) = self.list_state.selected() else {
+            return;
+        };
+        let Some(Selection::Entry(entry)) = self.selected() else {
+            return;
+        };
+        if entry.is_track {
+            return;
+        }
+
+        let entry = entry.clone();
+        self.trail.push((entry, row));
+        let scope = &self.trail[self.trail.len() - 1].0.tracks;
+        self.entries = self.children(scope, tracks);
+        self.select_first_entry();
+    }
+
+    // Returns false when already at the root.
+    pub fn leave(&mut self, tracks: &[Audio]) -> bool {
+        let Some((_, row)) = self.trail.pop() else {
+            return false;
+        };
+
+        self.entries = match self.trail.last() {
+            Some((parent, _)) => self.children(&parent.tracks, tracks),
+            None => self.children(&(0..tracks.len()).collect::<Vec<_>>(), tracks),
+        };
+        self.list_state.select(Some(row));
+        true
+    }
+
+    pub fn breadcrumbs(&self) -> String {
+        let mut crumbs = vec![self.root.to_string()];
+        crumbs.extend(self.trail.iter().map(|(entry, _)| entry.label.clone()));
+        crumbs.join(" › ")
+    }
+
+    fn level(&self) -> Level {
+        let levels = self.root.levels();
+        levels[self.trail.len().min(levels.len() - 1)]
+    }
+
+    fn current_folder(&self) -> PathBuf {
+        self.trail
+            .iter()
+            .fold(self.library_path.clone(), |path, (entry, _)| path.join(&entry.label))
+    }
+
+    fn children(&self, scope: &[usize], tracks: &[Audio]) -> Vec<Entry> {
+        match self.level() {
+            Level::Folder => folder_children(&self.current_folder(), scope, tracks),
+            level => children(level, scope, tracks),
+        }
+    }
+
+    // Everything on the current level, in the order it is listed.
+    fn listed_tracks(&self) -> Vec<usize> {
+        self.entries
+            .iter()
+            .flat_map(|entry| entry.tracks.iter().copied())
+            .collect()
+    }
+
+    fn track_entries(&self) -> Vec<usize> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.is_track)
+            .flat_map(|entry| entry.tracks.iter().copied())
+            .collect()
+    }
+
+    fn select_first_entry(&mut self) {
+        let actions = self.actions().len();
+        match self.entries.is_empty() {
+            true => self.list_state.select(Some(0)),
+            false => self.list_state.select(Some(actions)),
+        }
+    }
+}
+
+fn children(level: Level, scope: &[usize], tracks: &[Audio]) -> Vec<Entry> {
+    match level {
+        Level::Genre => group(scope, tracks, |track| track.genre.clone())
+            .into_iter()
+            .map(|(label, indices)| Entry {
+                label,
+                detail: count(indices.len(), "track"),
+                tracks: indices,
+                is_track: false,
+            })
+            .collect(),
+        Level::Decade => group(scope, tracks, |track| match track.year {
+            Some(year) => format!("{}s", year / 10 * 10),
+            None => String::from("Unknown"),
+        })
+        .into_iter()
+        .map(|(label, indices)| Entry {
+            label,
+            detail: count(indices.len(), "track"),
+            tracks: indices,
+            is_track: false,
+        })
+        .collect(),
+        Level::Year => group(scope, tracks, |track| match track.year {
+            Some(year) => year.to_string(),
+            None => String::from("Unknown"),
+        })
+        .into_iter()
+        .map(|(label, indices)| {
+            let albums: HashSet<String> = indices
+                .iter()
+                .map(|index| tracks[*index].album.to_lowercase())
+                .collect();
+            Entry {
+                label,
+                detail: format!(
+                    "{} · {}",
+                    count(albums.len(), "album"),
+                    count(indices.len(), "track")
+                ),
+                tracks: indices,
+                is_track: false,
+            }
+        })
+        .collect(),
+        Level::Artist => group(scope, tracks, |track| track.author.clone())
+            .into_iter()
+            .map(|(label, indices)| {
+                let albums: HashSet<String> = indices
+                    .iter()
+                    .map(|index| tracks[*index].album.to_lowercase())
+                    .collect();
+                Entry {
+                    label,
+                    detail: count(albums.len(), "album"),
+                    tracks: indices,
+                    is_track: false,
+                }
+            })
+            .collect(),
+        Level::Album => {
+            let mut albums: Vec<Entry> = group(scope, tracks, |track| track.album.clone())
+                .into_iter()
+                .map(|(label, indices)| {
+                    let year = indices.iter().find_map(|index| tracks[*index].year);
+                    let detail = match year {
+                        Some(year) => format!("{year} · {}", count(indices.len(), "track")),
+                        None => count(indices.len(), "track"),
+                    };
+                    Entry {
+                        label,
+                        detail,
+                        tracks: indices,
+                        is_track: false,
+                    }
+                })
+                .collect();
+            // Oldest first reads like a discography.
+            albums.sort_by_key(|entry| {
+                entry
+                    .tracks
+                    .iter()
+                    .find_map(|index| tracks[*index].year)
+                    .unwrap_or(u32::MAX)
+            });
+            albums
+        }
+        Level::Folder => Vec::new(),
+        Level::Track => {
+            let mut indices = scope.to_vec();
+            sort_by_album(&mut indices, tracks);
+            indices
+                .into_iter()
+                .map(|index| Entry {
+                    label: tracks[index].name.clone(),
+                    detail: tracks[index]
+                        .track_number
+                        .map(|number| format!("#{number}"))
+                        .unwrap_or_default(),
+                    tracks: vec![index],
+                    is_track: true,
+                })
+                .collect()
+        }
+    }
+}
+
+// Subfolders first, then the playable files sitting directly in `folder`.
+fn folder_children(folder: &Path, scope: &[usize], tracks: &[Audio]) -> Vec<Entry> {
+    let mut folders: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    let mut files: Vec<(String, usize)> = Vec::new();
+    for index in scope {
+        let Ok(relative) = tracks[*index].path.strip_prefix(folder) else {
+            continue;
+        };
+        let mut components = relative.components();
+        let Some(first) = components.next() else {
+            continue;
+        };
+        let name = first.as_os_str().to_string_lossy().to_string();
+        match components.next() {
+            Some(_) => folders.entry(name).or_default().push(*index),
+            None => files.push((name, *index)),
+        }
+    }
+    files.sort();
+
+    let folders = folders.into_iter().map(|(label, indices)| Entry {
+        label,
+        detail: count(indices.len(), "track"),
+        tracks: indices,
+        is_track: false,
+    });
+    let files = files.into_iter().map(|(label, index)| Entry {
+        label,
+        detail: tracks[index].name.clone(),
+        tracks: vec![index],
+        is_track: true,
+    });
+    folders.chain(files).collect()
+}
+
+// Groups case-insensitively, but shows the first spelling that was found.
+fn group<F>(scope: &[usize], tracks: &[Audio], key: F) -> Vec<(String, Vec<usize>)>
+where
+    F: Fn(&Audio) -> String,
+{
+    let mut groups: BTreeMap<String, (String, Vec<usize>)> = BTreeMap::new();
+    for index in scope {
+        let label = key(&tracks[*index]);
+        groups
+            .entry(label.to_lowercase())
+            .or_insert_with(|| (label, Vec::new()))
+            .1
+            .push(*index);
+    }
+
+    groups
+        .into_values()
+        .map(|(label, mut indices)| {
+            sort_by_album(&mut indices, tracks);
+            (label, indices)
+        })
+        .collect()
+}
+
+fn sort_by_album(indices: &mut [usize], tracks: &[Audio]) {
+    indices.sort_by(|a, b| {
+        let (a, b) = (&tracks[*a], &tracks[*b]);
+        a.album
+            .to_lowercase()
+            .cmp(&b.album.to_lowercase())
+            .then(a.track_number.cmp(&b.track_number))
+            .then(a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+}
+
+fn count(number: usize, noun: &str) -> String {
+    match number {
+        1 => format!("1 {noun}"),
+        _ => format!("{number} {noun}s"),
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+
+use super::*;
+
+fn track(name: &str, author: &str, album: &str, year: u32, number: u32) -> Audio {
+    Audio {
+        name: String::from(name),
+        author: String::from(author),
+        album: String::from(album),
+        year: Some(year),
+        track_number: Some(number),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_drill_down() {
+    let tracks = vec![
+        track("Airbag", "Radiohead", "OK Computer", 1997, 1),
+        track("Reckoner", "Radiohead", "In Rainbows", 2007, 7),
+        track("Paranoid Android", "radiohead", "OK Computer", 1997, 2),
+        track("Hello", "Adele", "25", 2015, 1),
+    ];
+
+    let mut browser = Browser::open(BrowseRoot::Artists, &tracks, Path::new(""));
+    assert_eq!(browser.entries.len(), 2);
+    assert_eq!(browser.entries[1].label, "Radiohead");
+    assert_eq!(browser.entries[1].detail, "2 albums");
+
+    browser.list_state.select(Some(2));
+    browser.enter(&tracks);
+    assert_eq!(browser.breadcrumbs(), "Artists › Radiohead");
+    assert_eq!(browser.entries[0].label, "OK Computer");
+    assert_eq!(browser.entries[0].detail, "1997 · 2 tracks");
+    assert_eq!(browser.action_scope(BrowseAction::PlayAll, &tracks), vec![0, 2, 1]);
+
+    browser.list_state.select(Some(1));
+    browser.enter(&tracks);
+    assert!(browser.entries.iter().all(|entry| entry.is_track));
+    assert_eq!(browser.action_scope(BrowseAction::PlayAll, &tracks), vec![0, 2]);
+
+    browser.list_state.select(Some(2));
+    assert_eq!(browser.play_from_selected(), Some((vec![0, 2], 1)));
+
+    assert!(browser.leave(&tracks));
+    assert!(browser.leave(&tracks));
+    assert_eq!(browser.list_state.selected(), Some(2));
+    assert!(!browser.leave(&tracks));
+}
+
+#[test]
+fn test_decades() {
+    let tracks = vec![
+        track("Airbag", "Radiohead", "OK Computer", 1997, 1),
+        track("Reckoner", "Radiohead", "In Rainbows", 2007, 7),
+        track("Karma Police", "Radiohead", "OK Computer", 1997, 6),
+        Audio::default(),
+    ];
+
+    let mut browser = Browser::open(BrowseRoot::Years, &tracks, Path::new(""));
+    let labels: Vec<&str> = browser.entries.iter().map(|e| e.label.as_str()).collect();
+    assert_eq!(labels, vec!["1990s", "2000s", "Unknown"]);
+    assert_eq!(browser.actions(), vec![BrowseAction::PlayAll]);
+
+    browser.enter(&tracks);
+    assert_eq!(browser.action_label(BrowseAction::PlayDecade), "Play the 1990s");
+    assert_eq!(browser.entries[0].detail, "1 album · 2 tracks");
+
+    // Deeper down it still plays the whole decade.
+    browser.enter(&tracks);
+    assert_eq!(browser.action_label(BrowseAction::PlayDecade), "Play the 1990s");
+    assert_eq!(browser.action_scope(BrowseAction::PlayDecade, &tracks), vec![0, 2]);
+}
+
+#[test]
+fn test_folders() {
+    let file = |path: &str| Audio {
+        path: PathBuf::from(path),
+        ..Default::default()
+    };
+    let tracks = vec![
+        file("/music/sets/2024/b.flac"),
+        file("/music/sets/a.mp3"),
+        file("/music/field/rain.flac"),
+        file("/music/loose.mp3"),
+        file("/music/sets/2024/a.flac"),
+    ];
+
+    let mut browser = Browser::open(BrowseRoot::Folders, &tracks, Path::new("/music"));
+    let labels: Vec<&str> = browser.entries.iter().map(|e| e.label.as_str()).collect();
+    assert_eq!(labels, vec!["field", "sets", "loose.mp3"]);
+    assert_eq!(browser.action_scope(BrowseAction::PlayFolder, &tracks), vec![3]);
+
+    browser.list_state.select(Some(3));
+    browser.enter(&tracks);
+    assert_eq!(browser.breadcrumbs(), "Folders › sets");
+    assert_eq!(browser.action_scope(BrowseAction::PlayFolder, &tracks), vec![1]);
+    assert_eq!(
+        browser.action_scope(BrowseAction::PlayRecursively, &tracks),
+        vec![4, 0, 1]
+    );
+}
@@ -1,8 +1,15 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
-use crate::fuzzy_search::search;
+use crate::browse::{BrowseAction, BrowseRoot, Browser, Selection};
+use crate::lyrics::Lyrics;
+use crate::menu::{ConfigOption, config_options};
 use crate::order::Order;
-use crate::utility::order_by;
+use crate::spectrum::Visualizer;
+use crate::utility::{
+    config_dir, enqueue, order_by, order_shuffle, play_from_library, play_queue, rate,
+    remove_track, shuffle_by, skip,
+};
 use crate::{Action, Command, PlayerState, play_new_track};
 use crossterm::event::{self, KeyEvent};
 
@@ -15,10 +22,10 @@ pub(crate) fn handle_config(key: KeyEvent, state: &mut PlayerState) -> Action {
         event::KeyCode::Char(char) => match char {
             'j' => {
                 state.iteration_count = 0;
-                if let Some(selected_index) = state.list_state.selected() {
-                    if selected_index < 3 {
-                        state.list_state.select_next();
-                    }
+                if let Some(selected_index) = state.list_state.selected()
+                    && selected_index + 1 < config_options(&state.sort_presets, &state.themes).len()
+                {
+                    state.list_state.select_next();
                 }
             }
             'k' => {
@@ -27,62 +34,7 @@ pub(crate) fn handle_config(key: KeyEvent, state: &mut PlayerState) -> Action {
             }
             ' ' => {
                 if let Some(index) = state.list_state.selected() {
-                    match index {
-                        0 => {
-                            match order_by( &Order::Shuffle, &state.playback_order, &mut state.tracks,) 
-                            {
-                                Some(index) => {
-                                    state.current_track_index = Some(index);
-                                    state.playback_order = Order::Shuffle;
-                                }
-                                None => (),
-                            }
-                        }
-                        1 => {
-                            match order_by(&Order::Album, &state.playback_order, &mut state.tracks)
-                            {
-                                Some(index) => {
-                                    state.current_track_index = Some(index);
-                                    state.playback_order = Order::Album;
-                                }
-                                None => (),
-                            }
-                        }
-                        2 => {
-                            match order_by(&Order::Artist, &state.playback_order, &mut state.tracks)
-                            {
-                                Some(index) => {
-                                    state.current_track_index = Some(index);
-                                    state.playback_order = Order::Artist;
-                                }
-                                None => (),
-                            }
-                        }
-
-                        3 => {
-                            match order_by(&Order::Track, &state.playback_order, &mut state.tracks)
-                            {
-                                Some(index) => {
-                                    state.current_track_index = Some(index);
-                                    state.playback_order = Order::Track;
-                                }
-                                None => (),
-                            }
-                        }
-                        _ => {
-                            match order_by(
-                                &Order::Shuffle,
-                                &state.playback_order,
-                                &mut state.tracks,
-                            ) {
-                                Some(index) => {
-                                    state.current_track_index = Some(index);
-                                    state.playback_order = Order::Shuffle;
-                                }
-                                None => (),
-                            }
-                        }
-                    }
+                    apply_option(index, state);
                 }
                 return Action::Submit;
             }
@@ -93,91 +45,570 @@ pub(crate) fn handle_config(key: KeyEvent, state: &mut PlayerState) -> Action {
         }
         event::KeyCode::Enter => {
             if let Some(index) = state.list_state.selected() {
-                match index {
-                        0 => {
-                            match order_by( &Order::Shuffle, &state.playback_order, &mut state.tracks,) 
-                            {
-                                Some(index) => {
-                                    state.current_track_index = Some(index);
-                                    state.playback_order = Order::Shuffle;
-                                }
-                                None => (),
-                            }
-                        }
-                        1 => {
-                            match order_by(&Order::Album, &state.playback_order, &mut state.tracks)
-                            {
-                                Some(index) => {
-                                    state.current_track_index = Some(index);
-                                    state.playback_order = Order::Album;
-                                }
-                                None => (),
-                            }
-                        }
-                        2 => {
-                            match order_by(&Order::Artist, &state.playback_order, &mut state.tracks)
-                            {
-                                Some(index) => {
-                                    state.current_track_index = Some(index);
-                                    state.playback_order = Order::Artist;
-                                }
-                                None => (),
-                            }
-                        }
+                apply_option(index, state);
+            }
+            return Action::Submit;
+        }
+        _ => {}
+    };
+    Action::None
+}
+
+fn apply_option(selected: usize, state: &mut PlayerState) {
+    match config_options(&state.sort_presets, &state.themes)
+        .into_iter()
+        .nth(selected)
+    {
+        Some(ConfigOption::Order(order)) => change_order(order, state),
+        Some(ConfigOption::Repeat(repeat)) => state.repeat = repeat,
+        Some(ConfigOption::Rate) => {
+            if let Some(index) = target_track(state) {
+                let rating = state.stats.get(&state.tracks[index].path).rating;
+                rate(index, next_rating(rating), state);
+            }
+        }
+        Some(ConfigOption::Favourite) => {
+            if let Some(index) = target_track(state) {
+                state.stats.toggle_favourite(&state.tracks[index].path);
+            }
+        }
+        Some(ConfigOption::Browse(root)) => open_browser(root, state),
+        Some(ConfigOption::Queue) => open_queue(state),
+        Some(ConfigOption::History) => open_history(state),
+        Some(ConfigOption::Playlists) => open_playlists(state),
+        Some(ConfigOption::AddToPlaylist) => {
+            if let Some(index) = target_track(state) {
+                add_to_playlist(index, state);
+            }
+        }
+        Some(ConfigOption::Lyrics) => toggle_lyrics(state),
+        Some(ConfigOption::Visualizer) => next_visualizer(state),
+        Some(ConfigOption::RemainingTime) => state.show_remaining = !state.show_remaining,
+        Some(ConfigOption::Theme(name)) => {
+            if let Some(theme) = state.themes.iter().find(|theme| theme.name == name) {
+                state.theme = theme.clone();
+            }
+        }
+        None => {}
+    }
+}
+
+// The highlighted row while the table is showing, the playing track otherwise.
+fn target_track(state: &PlayerState) -> Option<usize> {
+    match state.is_choosing {
+        true => state
+            .table_state
+            .selected()
+            .and_then(|row| state.library.get(row).copied()),
+        false => state.current_track_index,
+    }
+}
+
+fn open_browser(root: BrowseRoot, state: &mut PlayerState) {
+    if state.browser.root != root {
+        state.browser = Browser::open(root, &state.tracks, &state.library_path);
+    }
+    state.iteration_count = 0;
+    state.is_browsing = true;
+}
 
-                        3 => {
-                            match order_by(&Order::Track, &state.playback_order, &mut state.tracks)
-                            {
-                                Some(index) => {
-                                    state.current_track_index = Some(index);
-                                    state.playback_order = Order::Track;
-                                }
-                                None => (),
-                            }
+// Shuffling only touches the queue, sorting reorders the library table. The queue follows the
+// table only when it was played from there, one put together by hand stays as it is.
+fn change_order(order: Order, state: &mut PlayerState) {
+    if order == state.playback_order {
+        return;
+    }
+    match order.is_shuffle() {
+        true => state
+            .queue
+            .shuffle(|rest| shuffle_by(&order, rest, &state.tracks, &state.stats)),
+        false => {
+            order_by(&order, &mut state.library, &state.tracks, &state.stats);
+            if state.queue.is_from_library
+                && let Some(current) = state.current_track_index
+                && let Some(row) = state.library.iter().position(|index| *index == current)
+            {
+                state.queue.replace(state.library.clone(), row);
+                state.queue.is_from_library = true;
+            }
+        }
+    }
+    state.playback_order = order;
+}
+
+pub(crate) fn handle_browse(key: KeyEvent, state: &mut PlayerState) -> Action {
+    match key.code {
+        event::KeyCode::Esc => match state.browser.leave(&state.tracks) {
+            true => state.iteration_count = 0,
+            false => return Action::Escape,
+        },
+        event::KeyCode::Char(char) => match char {
+            'j' => {
+                state.iteration_count = 0;
+                if let Some(selected_index) = state.browser.list_state.selected()
+                    && selected_index + 1 < state.browser.number_of_rows()
+                {
+                    state.browser.list_state.select_next();
+                }
+            }
+            'k' => {
+                state.iteration_count = 0;
+                state.browser.list_state.select_previous();
+            }
+            ':' => {
+                state.iteration_count = 0;
+                match state.browser.selected() {
+                    Some(Selection::Action(action)) => {
+                        let mut scope = state.browser.action_scope(action, &state.tracks);
+                        if action == BrowseAction::ShuffleGenre {
+                            order_shuffle(&mut scope);
                         }
-                        _ => {
-                            match order_by(
-                                &Order::Shuffle,
-                                &state.playback_order,
-                                &mut state.tracks,
-                            ) {
-                                Some(index) => {
-                                    state.current_track_index = Some(index);
-                                    state.playback_order = Order::Shuffle;
-                                }
-                                None => (),
-                            }
+                        play_queue(scope, 0, state);
+                        return Action::Submit;
+                    }
+                    Some(Selection::Entry(entry)) if entry.is_track => {
+                        if let Some((scope, start)) = state.browser.play_from_selected() {
+                            play_queue(scope, start, state);
                         }
+                        return Action::Submit;
+                    }
+                    Some(Selection::Entry(_)) => state.browser.enter(&state.tracks),
+                    None => {}
                 }
             }
-            return Action::Submit;
-        }
+            _ => {}
+        },
         _ => {}
-    };
+    }
     Action::None
 }
 
 pub(crate) fn handle_search(key: KeyEvent, state: &mut PlayerState) -> Action {
+    if state.is_picking_result {
+        return handle_search_results(key, state);
+    }
     match key.code {
         event::KeyCode::Char(c) => {
             state.keyword.push(c);
-            state.matched_tracks = search(&state.tracks, &state.keyword);
+            state.matched_tracks = state.search_index.search(&state.keyword);
         }
         event::KeyCode::Backspace => {
             state.keyword.pop();
-            state.matched_tracks = search(&state.tracks, &state.keyword);
+            state.matched_tracks = state.search_index.search(&state.keyword);
         }
         event::KeyCode::Esc => {
             return Action::Escape;
         }
-        event::KeyCode::Enter => {
-            return Action::Submit;
+        event::KeyCode::Enter if !state.matched_tracks.is_empty() => {
+            state.is_picking_result = true;
+            state.search_state.select_first();
         }
         _ => {}
     };
     Action::None
 }
 
+// Once the keyword is submitted, the keys act on the matched tracks instead of typing.
+fn handle_search_results(key: KeyEvent, state: &mut PlayerState) -> Action {
+    let selected = state
+        .search_state
+        .selected()
+        .filter(|row| *row < state.matched_tracks.len());
+    match key.code {
+        event::KeyCode::Esc => {
+            state.is_picking_result = false;
+            return Action::Escape;
+        }
+        event::KeyCode::Char(char) => match char {
+            'j' => {
+                if let Some(row) = selected
+                    && row + 1 < state.matched_tracks.len()
+                {
+                    state.search_state.select_next();
+                }
+            }
+            'k' => state.search_state.select_previous(),
+            '/' => state.is_picking_result = false,
+            ':' => {
+                if let Some(row) = selected {
+                    state.is_picking_result = false;
+                    play_queue(state.matched_tracks.clone(), row, state);
+                    return Action::Submit;
+                }
+            }
+            'a' => {
+                if let Some(row) = selected {
+                    enqueue(state.matched_tracks[row], false, state);
+                }
+            }
+            'A' => {
+                if let Some(row) = selected {
+                    enqueue(state.matched_tracks[row], true, state);
+                }
+            }
+            'P' => {
+                if let Some(row) = selected {
+                    add_to_playlist(state.matched_tracks[row], state);
+                }
+            }
+            _ => {}
+        },
+        _ => {}
+    }
+    Action::None
+}
+
+pub(crate) fn handle_queue(key: KeyEvent, state: &mut PlayerState) -> Action {
+    let start = state.queue.upcoming_start();
+    let position = state.queue_state.selected().map(|row| start + row);
+    match key.code {
+        event::KeyCode::Esc => {
+            state.is_moving_entry = false;
+            return Action::Escape;
+        }
+        event::KeyCode::Char(char) => match char {
+            'j' | 'k' => {
+                state.iteration_count = 0;
+                let down = char == 'j';
+                match (state.is_moving_entry, position) {
+                    (true, Some(position)) => {
+                        let moved = state.queue.move_entry(position, down);
+                        state.queue_state.select(Some(moved - start));
+                    }
+                    (false, Some(position)) if down => {
+                        if position + 1 < state.queue.tracks.len() {
+                            state.queue_state.select_next();
+                        }
+                    }
+                    _ => state.queue_state.select_previous(),
+                }
+            }
+            ':' => {
+                state.iteration_count = 0;
+                state.is_moving_entry = !state.is_moving_entry;
+            }
+            '<' => {
+                state.iteration_count = 0;
+                state.is_moving_entry = false;
+                if let Some(position) = position {
+                    state.queue.remove(position);
+                }
+            }
+            '>' => {
+                state.is_moving_entry = false;
+                if let Some(position) = position
+                    && let Some(index) = state.queue.jump(position)
+                {
+                    play_new_track(index, state);
+                    state.queue_state.select_first();
+                }
+            }
+            _ => {}
+        },
+        _ => {}
+    }
+    Action::None
+}
+
+fn open_queue(state: &mut PlayerState) {
+    state.iteration_count = 0;
+    state.is_moving_entry = false;
+    state.queue_state.select_first();
+    state.is_viewing_queue = true;
+}
+
+pub(crate) fn handle_history(key: KeyEvent, state: &mut PlayerState) -> Action {
+    let selected = state
+        .history_state
+        .selected()
+        .filter(|row| *row < state.recently_played.len());
+    match key.code {
+        event::KeyCode::Esc => return Action::Escape,
+        event::KeyCode::Char(char) => match char {
+            'j' => {
+                state.iteration_count = 0;
+                if let Some(row) = selected
+                    && row + 1 < state.recently_played.len()
+                {
+                    state.history_state.select_next();
+                }
+            }
+            'k' => {
+                state.iteration_count = 0;
+                state.history_state.select_previous();
+            }
+            ':' => {
+                if let Some(row) = selected {
+                    let tracks = state.recently_played.iter().map(|(index, _)| *index);
+                    play_queue(tracks.collect(), row, state);
+                    return Action::Submit;
+                }
+            }
+            'a' => {
+                state.iteration_count = 0;
+                if let Some(row) = selected {
+                    enqueue(state.recently_played[row].0, false, state);
+                }
+            }
+            'A' => {
+                state.iteration_count = 0;
+                if let Some(row) = selected {
+                    enqueue(state.recently_played[row].0, true, state);
+                }
+            }
+            _ => {}
+        },
+        _ => {}
+    }
+    Action::None
+}
+
+// Tracks that left the library since they were played are left out.
+fn open_history(state: &mut PlayerState) {
+    let positions: HashMap<&Path, usize> = state
+        .tracks
+        .iter()
+        .enumerate()
+        .map(|(index, track)| (track.path.as_path(), index))
+        .collect();
+    state.recently_played = state
+        .history
+        .recently_played(100)
+        .into_iter()
+        .filter_map(|listen| {
+            let index = positions.get(listen.path.as_path())?;
+            Some((*index, listen.time))
+        })
+        .collect();
+    state.iteration_count = 0;
+    state.history_state.select_first();
+    state.is_viewing_history = true;
+}
+
+pub(crate) fn handle_playlists(key: KeyEvent, state: &mut PlayerState) -> Action {
+    if state.is_naming_playlist {
+        return handle_playlist_name(key, state);
+    }
+    if let Some(list) = state.opened_playlist {
+        return handle_playlist_entries(key, list, state);
+    }
+
+    // The first row makes a new playlist, the playlists follow it.
+    let selected = state.playlist_state.selected();
+    let list = selected
+        .and_then(|row| row.checked_sub(1))
+        .filter(|list| *list < state.playlists.lists.len());
+    match key.code {
+        event::KeyCode::Esc => return Action::Escape,
+        event::KeyCode::Char(char) => match char {
+            'j' => {
+                state.iteration_count = 0;
+                if let Some(row) = selected
+                    && row < state.playlists.lists.len()
+                {
+                    state.playlist_state.select_next();
+                }
+            }
+            'k' => {
+                state.iteration_count = 0;
+                state.playlist_state.select_previous();
+            }
+            ':' => match list {
+                Some(list) => {
+                    let tracks: Vec<usize> = state
+                        .playlists
+                        .resolve(list, &state.tracks)
+                        .into_iter()
+                        .flatten()
+                        .collect();
+                    if !tracks.is_empty() {
+                        state.playlists.set_target(list);
+                        play_queue(tracks, 0, state);
+                        return Action::Submit;
+                    }
+                }
+                // Named like "New playlist 2" so the buttons alone can make one, the keyboard
+                // can rename it afterwards.
+                None => {
+                    let list = state.playlists.create("New playlist");
+                    state.playlist_state.select(Some(list + 1));
+                }
+            },
+            '>' => {
+                state.iteration_count = 0;
+                if let Some(list) = list {
+                    state.playlists.set_target(list);
+                    state.opened_playlist = Some(list);
+                    state.playlist_state.select_first();
+                }
+            }
+            'r' => {
+                if let Some(list) = list {
+                    start_naming(list, state);
+                }
+            }
+            'D' => {
+                state.iteration_count = 0;
+                if let (Some(row), Some(list)) = (selected, list) {
+                    state.playlists.delete(list);
+                    state
+                        .playlist_state
+                        .select(Some(row.min(state.playlists.lists.len())));
+                }
+            }
+            'e' => {
+                state.iteration_count = 0;
+                if let Some(list) = list
+                    && let Some(dir) = config_dir()
+                {
+                    state.playlist_notice =
+                        match state
+                            .playlists
+                            .export(list, &state.tracks, &dir.join("playlists"))
+                        {
+                            Ok(path) => format!("Exported to {}", path.display()),
+                            Err(_) => String::from("Can't export the playlist"),
+                        };
+                }
+            }
+            _ => {}
+        },
+        _ => {}
+    }
+    Action::None
+}
+
+fn handle_playlist_entries(key: KeyEvent, list: usize, state: &mut PlayerState) -> Action {
+    let entries = state.playlists.resolve(list, &state.tracks);
+    let selected = state
+        .playlist_state
+        .selected()
+        .filter(|row| *row < entries.len());
+    match key.code {
+        event::KeyCode::Esc => {
+            state.iteration_count = 0;
+            state.opened_playlist = None;
+            state.playlist_state.select(Some(list + 1));
+        }
+        event::KeyCode::Char(char) => match char {
+            'j' => {
+                state.iteration_count = 0;
+                if let Some(row) = selected
+                    && row + 1 < entries.len()
+                {
+                    state.playlist_state.select_next();
+                }
+            }
+            'k' => {
+                state.iteration_count = 0;
+                state.playlist_state.select_previous();
+            }
+            ':' => {
+                if let Some(row) = selected {
+                    // Missing files are dropped, so the start moves up past them.
+                    let start = entries[..row].iter().flatten().count();
+                    let tracks: Vec<usize> = entries.into_iter().flatten().collect();
+                    if start < tracks.len() {
+                        play_queue(tracks, start, state);
+                        return Action::Submit;
+                    }
+                }
+            }
+            '<' => {
+                state.iteration_count = 0;
+                if let Some(row) = selected {
+                    state.playlists.remove_entry(list, row);
+                    let last = state.playlists.lists[list].tracks.len().saturating_sub(1);
+                    state.playlist_state.select(Some(row.min(last)));
+                }
+            }
+            _ => {}
+        },
+        _ => {}
+    }
+    Action::None
+}
+
+fn handle_playlist_name(key: KeyEvent, state: &mut PlayerState) -> Action {
+    match key.code {
+        event::KeyCode::Char(char) => state.playlist_name.push(char),
+        event::KeyCode::Backspace => {
+            state.playlist_name.pop();
+        }
+        event::KeyCode::Esc => state.is_naming_playlist = false,
+        event::KeyCode::Enter => {
+            if let Some(list) = state.renamed_playlist {
+                state.playlists.rename(list, &state.playlist_name);
+            }
+            state.is_naming_playlist = false;
+        }
+        _ => {}
+    }
+    state.iteration_count = 0;
+    Action::None
+}
+
+// Only reachable from the keyboard, the buttons can't type.
+fn start_naming(list: usize, state: &mut PlayerState) {
+    state.playlist_name = state.playlists.lists[list].name.clone();
+    state.renamed_playlist = Some(list);
+    state.is_naming_playlist = true;
+}
+
+fn open_playlists(state: &mut PlayerState) {
+    state.iteration_count = 0;
+    state.playlists.refresh_smart(&state.tracks, &state.stats);
+    state.playlist_notice.clear();
+    state.opened_playlist = None;
+    state.is_naming_playlist = false;
+    state.playlist_state.select_first();
+    state.is_viewing_playlists = true;
+}
+
+fn add_to_playlist(index: usize, state: &mut PlayerState) {
+    if let Some(track) = state.tracks.get(index) {
+        state.playlists.add(&track.path);
+    }
+}
+
+// Takes the place of the player screen, so everything but scrolling works as it does there.
+// Synced lyrics follow the track by themselves and leave j and k to the player.
+pub(crate) fn handle_lyrics(key: KeyEvent, state: &mut PlayerState) -> Action {
+    let lines = state.current_track_index.and_then(|index| {
+        let lyrics = state.lyrics.borrow();
+        match lyrics.get(&state.tracks[index].path)? {
+            Lyrics::Synced(_) => None,
+            Lyrics::Unsynced(lines) => Some(lines.len()),
+        }
+    });
+    match (key.code, lines) {
+        (event::KeyCode::Esc | event::KeyCode::Char('l'), _) => return Action::Escape,
+        (event::KeyCode::Char('j'), Some(lines)) => {
+            if state.lyrics_scroll + 1 < lines {
+                state.lyrics_scroll += 1;
+            }
+        }
+        (event::KeyCode::Char('k'), Some(_)) => {
+            state.lyrics_scroll = state.lyrics_scroll.saturating_sub(1);
+        }
+        _ => return handle_playback(key, state),
+    }
+    Action::None
+}
+
+fn next_visualizer(state: &mut PlayerState) {
+    state.visualizer = state.visualizer.next();
+    let is_on = state.visualizer != Visualizer::Off;
+    state.tx.send(Command::Visualize(is_on)).unwrap_or(());
+}
+
+// Choosing the option again from the lyrics screen is how the buttons get back to the player.
+fn toggle_lyrics(state: &mut PlayerState) {
+    state.lyrics_scroll = 0;
+    state.is_choosing = false;
+    state.is_viewing_lyrics = !state.is_viewing_lyrics;
+}
+
 pub(crate) fn handle_playback(key: KeyEvent, state: &mut PlayerState) -> Action {
     match key.code {
         event::KeyCode::Tab => state.is_configuring = !state.is_configuring,
@@ -195,18 +626,39 @@ pub(crate) fn handle_playback(key: KeyEvent, state: &mut PlayerState) -> Action
             '/' => {
                 state.is_searching = true;
             }
+            'a' | 'q' => open_queue(state),
+            'l' => toggle_lyrics(state),
+            't' => state.show_remaining = !state.show_remaining,
+            'v' => next_visualizer(state),
+            '0'..='5' => {
+                if let Some(index) = target_track(state) {
+                    rate(index, stars(char), state);
+                }
+            }
+            'f' => {
+                if let Some(index) = target_track(state) {
+                    state.stats.toggle_favourite(&state.tracks[index].path);
+                }
+            }
+            'P' => {
+                if let Some(index) = target_track(state) {
+                    add_to_playlist(index, state);
+                }
+            }
             'D' => {
-                if let Some(index) = state.table_state.selected() {
-                    state.tracks.remove(index);
+                if let Some(row) = state.table_state.selected()
+                    && let Some(index) = state.library.get(row).copied()
+                {
+                    remove_track(index, state);
                 }
             }
             'j' => {
                 state.is_choosing = true;
                 state.iteration_count = 0;
-                if let Some(selected_index) = state.table_state.selected() {
-                    if selected_index < state.number_of_tracks - 1 {
-                        state.table_state.select_next();
-                    }
+                if let Some(selected_index) = state.table_state.selected()
+                    && selected_index + 1 < state.number_of_tracks
+                {
+                    state.table_state.select_next();
                 }
             }
             'k' => {
@@ -215,18 +667,10 @@ pub(crate) fn handle_playback(key: KeyEvent, state: &mut PlayerState) -> Action
                 state.table_state.select_previous();
             }
             'p' => {
-                if let Some(mut index) = state.current_track_index {
-                    state.tracks[index].is_playing = false;
-                    index = (index + state.number_of_tracks - 1) % state.number_of_tracks;
-                    play_new_track(index, state);
-                }
+                skip(state, false);
             }
             'n' => {
-                if let Some(mut index) = state.current_track_index {
-                    state.tracks[index].is_playing = false;
-                    index = (index + 1) % state.number_of_tracks;
-                    play_new_track(index, state);
-                }
+                skip(state, true);
             }
             '<' => {
                 state
@@ -274,40 +718,76 @@ pub(crate) fn handle_choosing(key: KeyEvent, state: &mut PlayerState) -> Action
             }
             ':' => {
                 state.is_choosing = false;
-                if let Some(index) = state.table_state.selected() {
+                if let Some(row) = state.table_state.selected()
+                    && row < state.library.len()
+                {
                     match state.current_track_index {
-                        Some(current_index) => {
-                            if index == current_index {
-                                state
-                                    .tx
-                                    .send(Command::PlayPause(PathBuf::new()))
-                                    .unwrap_or(());
-                            } else {
-                                state.tracks[current_index].is_playing = false;
-                                play_new_track(index, state);
-                            }
-                        }
-                        None => {
-                            play_new_track(index, state);
+                        Some(current_index) if state.library[row] == current_index => {
+                            state
+                                .tx
+                                .send(Command::PlayPause(PathBuf::new()))
+                                .unwrap_or(());
                         }
+                        _ => play_from_library(row, state),
                     }
                 }
             }
             '/' => {
                 state.is_searching = true;
             }
+            'q' => open_queue(state),
+            'a' => {
+                if let Some(row) = state.table_state.selected()
+                    && let Some(index) = state.library.get(row).copied()
+                {
+                    enqueue(index, false, state);
+                }
+            }
+            'A' => {
+                if let Some(row) = state.table_state.selected()
+                    && let Some(index) = state.library.get(row).copied()
+                {
+                    enqueue(index, true, state);
+                }
+            }
+            '0'..='5' => {
+                state.iteration_count = 0;
+                if let Some(row) = state.table_state.selected()
+                    && let Some(index) = state.library.get(row).copied()
+                {
+                    rate(index, stars(char), state);
+                }
+            }
+            'f' => {
+                state.iteration_count = 0;
+                if let Some(row) = state.table_state.selected()
+                    && let Some(index) = state.library.get(row).copied()
+                {
+                    state.stats.toggle_favourite(&state.tracks[index].path);
+                }
+            }
+            'P' => {
+                state.iteration_count = 0;
+                if let Some(row) = state.table_state.selected()
+                    && let Some(index) = state.library.get(row).copied()
+                {
+                    add_to_playlist(index, state);
+                }
+            }
             'D' => {
-                if let Some(index) = state.table_state.selected() {
-                    state.tracks.remove(index);
+                if let Some(row) = state.table_state.selected()
+                    && let Some(index) = state.library.get(row).copied()
+                {
+                    remove_track(index, state);
                 }
             }
             'j' => {
                 state.is_choosing = true;
                 state.iteration_count = 0;
-                if let Some(selected_index) = state.table_state.selected() {
-                    if selected_index < state.number_of_tracks - 1 {
-                        state.table_state.select_next();
-                    }
+                if let Some(selected_index) = state.table_state.selected()
+                    && selected_index + 1 < state.number_of_tracks
+                {
+                    state.table_state.select_next();
                 }
             }
             'k' => {
@@ -317,19 +797,11 @@ pub(crate) fn handle_choosing(key: KeyEvent, state: &mut PlayerState) -> Action
             }
             '<' => {
                 state.iteration_count = 0;
-                if let Some(mut index) = state.current_track_index {
-                    state.tracks[index].is_playing = false;
-                    index = (index + state.number_of_tracks - 1) % state.number_of_tracks;
-                    play_new_track(index, state);
-                }
+                skip(state, false);
             }
             '>' => {
                 state.iteration_count = 0;
-                if let Some(mut index) = state.current_track_index {
-                    state.tracks[index].is_playing = false;
-                    index = (index + 1) % state.number_of_tracks;
-                    play_new_track(index, state);
-                }
+                skip(state, true);
             }
             'K' => {
                 state.is_adjusting = true;
@@ -351,3 +823,23 @@ pub(crate) fn handle_choosing(key: KeyEvent, state: &mut PlayerState) -> Action
     }
     Action::None
 }
+
+// Every pick adds a star, past five it's cleared again.
+fn next_rating(rating: Option<u8>) -> Option<u8> {
+    match rating {
+        Some(5) => None,
+        Some(stars) => Some(stars + 1),
+        None => Some(1),
+    }
+}
+
+// '0' clears the rating, '1' to '5' give that many stars.
+fn stars(digit: char) -> Option<u8> {
+    digit
+        .to_digit(10)
+        .filter(|stars| *stars > 0)
+        .map(|stars| stars as u8)
+}
+
+#[cfg(test)]
+mod test;
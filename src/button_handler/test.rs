@@ -0,0 +1,88 @@
+use super::*;
+use crate::{Audio, handle_key};
+use crossterm::event::{KeyCode, KeyModifiers};
+
+fn track(name: &str) -> Audio {
+    Audio {
+        name: String::from(name),
+        path: PathBuf::from(format!("/music/{name}.flac")),
+        ..Default::default()
+    }
+}
+
+// What the GPIO thread sends for a button.
+fn press(button: char, state: &mut PlayerState) {
+    let key = KeyEvent::new(KeyCode::Char(button), KeyModifiers::NONE);
+    handle_key(key, state, true);
+}
+
+#[test]
+fn test_play_next_from_the_table() {
+    let mut state = PlayerState::detached(vec![track("a"), track("b"), track("c")]);
+    state.table_state.select_first();
+    press(':', &mut state);
+    assert_eq!(state.current_track_index, Some(0));
+
+    // The first press brings the table back, the second moves down.
+    press('j', &mut state);
+    press('j', &mut state);
+    assert_eq!(state.table_state.selected(), Some(2));
+    press('A', &mut state);
+    assert_eq!(state.queue.upcoming(), &[2, 1, 2]);
+    assert_eq!(state.current_track_index, Some(0));
+}
+
+// Opens the config screen from where the buttons are and applies the option.
+fn choose_option(option: ConfigOption, state: &mut PlayerState) {
+    press(' ', state);
+    let options = config_options(&state.sort_presets, &state.themes);
+    let label = option.to_string();
+    state.list_state.select(
+        options
+            .iter()
+            .position(|option| option.to_string() == label),
+    );
+    press(' ', state);
+}
+
+#[test]
+fn test_rate_from_the_table() {
+    let mut state = PlayerState::detached(vec![track("a"), track("b"), track("c")]);
+    state.table_state.select_first();
+    press(':', &mut state);
+    press('j', &mut state);
+    assert_eq!(state.table_state.selected(), Some(1));
+
+    choose_option(ConfigOption::Rate, &mut state);
+    choose_option(ConfigOption::Favourite, &mut state);
+    let path = |index: usize| state.tracks[index].path.clone();
+    assert_eq!(state.stats.get(&path(1)).rating, Some(1));
+    assert!(state.stats.get(&path(1)).favourite);
+    assert_eq!(state.stats.get(&path(0)), Default::default());
+    assert!(!state.is_configuring);
+}
+
+#[test]
+fn test_add_to_playlist_from_the_table() {
+    let mut state = PlayerState::detached(vec![track("a"), track("b"), track("c")]);
+    state.table_state.select_first();
+    press(':', &mut state);
+    press('j', &mut state);
+    press('j', &mut state);
+
+    choose_option(ConfigOption::AddToPlaylist, &mut state);
+    assert_eq!(state.playlists.lists.len(), 1);
+    assert_eq!(
+        state.playlists.lists[0].tracks,
+        vec![state.tracks[2].path.clone()]
+    );
+}
+
+#[test]
+fn test_remaining_time_from_the_config() {
+    let mut state = PlayerState::detached(vec![track("a")]);
+    choose_option(ConfigOption::RemainingTime, &mut state);
+    assert!(state.show_remaining);
+    choose_option(ConfigOption::RemainingTime, &mut state);
+    assert!(!state.show_remaining);
+}
@@ -0,0 +1,234 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, RgbImage};
+use lofty::file::TaggedFileExt;
+use lofty::picture::PictureType;
+use lofty::read_from_path;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::widgets::Widget;
+
+use crate::Audio;
+
+// Sidecar files looked for next to the track when there's no embedded picture.
+const SIDECARS: [&str; 4] = ["cover", "folder", "front", "album"];
+
+// Covers are shrunk to this once they're decoded, the display never needs more.
+const THUMBNAIL: u32 = 128;
+
+// A picture scaled to the terminal, two pixels per cell drawn with the upper half block.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CoverArt {
+    pub width: u16,
+    pub height: u16,
+    // The rows of cells, `height * 2` rows of pixels.
+    pub pixels: Vec<[u8; 3]>,
+    pub true_color: bool,
+}
+
+impl CoverArt {
+    // Keeps the aspect ratio, so it may come out narrower or shorter than the area.
+    pub fn scale(image: &RgbImage, width: u16, height: u16, true_color: bool) -> Self {
+        let (max_width, max_height) = (width as u32, height as u32 * 2);
+        let ratio = f64::min(
+            max_width as f64 / image.width() as f64,
+            max_height as f64 / image.height() as f64,
+        );
+        let pixel_width = ((image.width() as f64 * ratio) as u32).clamp(1, max_width.max(1));
+        let pixel_height = ((image.height() as f64 * ratio) as u32).clamp(1, max_height.max(1));
+        // Whole cells only.
+        let pixel_height = pixel_height + pixel_height % 2;
+
+        let scaled = imageops::resize(image, pixel_width, pixel_height, FilterType::Triangle);
+        CoverArt {
+            width: pixel_width as u16,
+            height: (pixel_height / 2) as u16,
+            pixels: scaled.pixels().map(|pixel| pixel.0).collect(),
+            true_color,
+        }
+    }
+
+    fn color(&self, x: u16, y: u16) -> Color {
+        let rgb = self.pixels[y as usize * self.width as usize + x as usize];
+        match self.true_color {
+            true => Color::Rgb(rgb[0], rgb[1], rgb[2]),
+            false => Color::Indexed(quantize(rgb)),
+        }
+    }
+}
+
+impl Widget for &CoverArt {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for row in 0..self.height.min(area.height) {
+            for column in 0..self.width.min(area.width) {
+                buf[(area.x + column, area.y + row)]
+                    .set_symbol("▀")
+                    .set_fg(self.color(column, row * 2))
+                    .set_bg(self.color(column, row * 2 + 1));
+            }
+        }
+    }
+}
+
+// The closest of the 6x6x6 colour cube and the grey ramp of the 256 colour palette.
+pub(crate) fn quantize(rgb: [u8; 3]) -> u8 {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let nearest_level = |value: u8| {
+        (0..LEVELS.len())
+            .min_by_key(|level| LEVELS[*level].abs_diff(value))
+            .unwrap_or(0)
+    };
+    let distance = |a: [u8; 3], b: [u8; 3]| -> u32 {
+        (0..3).map(|i| (a[i].abs_diff(b[i]) as u32).pow(2)).sum()
+    };
+
+    let [r, g, b] = rgb.map(nearest_level);
+    let cube = [LEVELS[r], LEVELS[g], LEVELS[b]];
+    let cube_index = 16 + 36 * r as u8 + 6 * g as u8 + b as u8;
+
+    let average = (rgb.iter().map(|value| *value as u32).sum::<u32>() / 3) as u8;
+    let step = (average.saturating_sub(3) / 10).min(23);
+    let grey = 8 + 10 * step;
+    let grey_index = 232 + step;
+
+    match distance(rgb, [grey; 3]) < distance(rgb, cube) {
+        true => grey_index,
+        false => cube_index,
+    }
+}
+
+// An album is told apart by its name and the directory it's in.
+type Key = (String, PathBuf);
+// The album and one of its tracks to read the cover from.
+type Request = (Key, PathBuf);
+type Loaded = (Key, Option<RgbImage>);
+
+// Every album is decoded once, and scaled again only when the space for it changes.
+struct Entry {
+    thumbnail: Option<RgbImage>,
+    // With the width and height it was scaled for.
+    scaled: Option<((u16, u16), CoverArt)>,
+}
+
+// Reading and decoding a cover takes long enough to stall the screen and the buttons, so it's
+// left to a background thread and nothing is drawn until it's done.
+pub(crate) struct CoverCache {
+    entries: HashMap<Key, Entry>,
+    pending: HashSet<Key>,
+    worker: Option<(Sender<Request>, Receiver<Loaded>)>,
+    true_color: bool,
+}
+
+impl Default for CoverCache {
+    fn default() -> Self {
+        let true_color = std::env::var("COLORTERM")
+            .is_ok_and(|term| term.contains("truecolor") || term.contains("24bit"));
+        CoverCache {
+            entries: HashMap::new(),
+            pending: HashSet::new(),
+            worker: None,
+            true_color,
+        }
+    }
+}
+
+impl CoverCache {
+    pub fn get(&mut self, track: &Audio, width: u16, height: u16) -> Option<&CoverArt> {
+        let directory = track.path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let key = (track.album.to_lowercase(), directory);
+        let Some(entry) = self.entries.get_mut(&key) else {
+            if self.pending.insert(key.clone()) {
+                let (tx, _) = self.worker.get_or_insert_with(spawn_worker);
+                tx.send((key, track.path.clone())).unwrap_or(());
+            }
+            return None;
+        };
+
+        let thumbnail = entry.thumbnail.as_ref()?;
+        let size = (width, height);
+        if entry
+            .scaled
+            .as_ref()
+            .is_none_or(|(scaled, _)| *scaled != size)
+        {
+            let art = CoverArt::scale(thumbnail, width, height, self.true_color);
+            entry.scaled = Some((size, art));
+        }
+        entry.scaled.as_ref().map(|(_, art)| art)
+    }
+
+    // Picks up whatever the background thread finished, called from the main loop.
+    pub fn receive(&mut self) {
+        let Some((_, rx)) = &self.worker else {
+            return;
+        };
+        let loaded: Vec<Loaded> = rx.try_iter().collect();
+        for (key, thumbnail) in loaded {
+            self.pending.remove(&key);
+            let entry = Entry {
+                thumbnail,
+                scaled: None,
+            };
+            self.entries.insert(key, entry);
+        }
+    }
+}
+
+fn spawn_worker() -> (Sender<Request>, Receiver<Loaded>) {
+    let (request_tx, request_rx) = mpsc::channel::<Request>();
+    let (loaded_tx, loaded_rx) = mpsc::channel::<Loaded>();
+    let _ = thread::Builder::new()
+        .name("cover".to_string())
+        .spawn(move || {
+            for (key, path) in request_rx {
+                if loaded_tx.send((key, load(&path))).is_err() {
+                    break;
+                }
+            }
+        });
+    (request_tx, loaded_rx)
+}
+
+fn load(path: &Path) -> Option<RgbImage> {
+    let bytes = embedded(path).or_else(|| sidecar(path))?;
+    let image = image::load_from_memory(&bytes).ok()?;
+    Some(DynamicImage::thumbnail(&image, THUMBNAIL, THUMBNAIL).to_rgb8())
+}
+
+// The front cover if it's marked as one, otherwise whatever picture comes first.
+fn embedded(path: &Path) -> Option<Vec<u8>> {
+    let tagged_file = read_from_path(path).ok()?;
+    let pictures = tagged_file.primary_tag()?.pictures();
+    pictures
+        .iter()
+        .find(|picture| picture.pic_type() == PictureType::CoverFront)
+        .or(pictures.first())
+        .map(|picture| picture.data().to_vec())
+}
+
+fn sidecar(path: &Path) -> Option<Vec<u8>> {
+    let files: Vec<PathBuf> = fs::read_dir(path.parent()?)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    SIDECARS.iter().find_map(|name| {
+        files.iter().find_map(|file| {
+            let stem = file.file_stem()?.to_str()?.to_lowercase();
+            let extension = file.extension()?.to_str()?.to_lowercase();
+            let is_image = matches!(extension.as_str(), "jpg" | "jpeg" | "png");
+            match stem == *name && is_image {
+                true => fs::read(file).ok(),
+                false => None,
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod test;
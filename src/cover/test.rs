@@ -0,0 +1,25 @@
+use super::*;
+
+#[test]
+fn test_quantize() {
+    assert_eq!(quantize([0, 0, 0]), 16);
+    assert_eq!(quantize([255, 255, 255]), 231);
+    assert_eq!(quantize([255, 0, 0]), 196);
+    // Greys land on the ramp rather than the cube.
+    assert_eq!(quantize([128, 128, 128]), 244);
+}
+
+#[test]
+fn test_scale_keeps_aspect() {
+    let wide = RgbImage::from_pixel(200, 100, image::Rgb([10, 20, 30]));
+    let art = CoverArt::scale(&wide, 20, 20, true);
+    // Twenty cells across, two pixels per cell down.
+    assert_eq!((art.width, art.height), (20, 5));
+    assert_eq!(art.pixels.len(), 20 * 10);
+
+    let mut buf = Buffer::empty(Rect::new(0, 0, 20, 20));
+    (&art).render(buf.area, &mut buf);
+    assert_eq!(buf[(0, 0)].symbol(), "▀");
+    assert_eq!(buf[(0, 0)].fg, Color::Rgb(10, 20, 30));
+    assert_eq!(buf[(0, 5)].symbol(), " ");
+}
@@ -1,22 +1,96 @@
+use std::collections::HashMap;
+
 use crate::Audio;
 
-// TODO: We can probably do a iter, filter, collect chain without intermediary value like
-// matches. Looks more rust-like that way.
+type Trigram = [char; 3];
 
 // Also this is not technically fuzzy. Probably needs bit more work.
-pub(crate) fn search(tracks: &Vec<Audio>, keyword: &str) -> Vec<Audio> {
-    // Create new vector to save search results in
-    let mut matches = Vec::new();
-
-    // Iterate over all tracks, check if match
-    for track in tracks {
-        if track.name.contains(keyword) || track.author.contains(keyword) {
-            // Add match to vector
-            matches.push(track.clone());
+// The index is built once after the library is loaded and only patched afterwards, so a
+// keystroke costs a lookup instead of a scan over every track.
+pub(crate) struct SearchIndex {
+    // Lowercased "name\nauthor" of every track, in the same order as `state.tracks`.
+    keys: Vec<String>,
+    // Sorted track indices for every trigram that appears in a key.
+    trigrams: HashMap<Trigram, Vec<usize>>,
+}
+
+impl SearchIndex {
+    pub fn build(tracks: &[Audio]) -> Self {
+        let mut index = SearchIndex {
+            keys: Vec::with_capacity(tracks.len()),
+            trigrams: HashMap::new(),
+        };
+        for track in tracks {
+            index.push(track);
+        }
+        index
+    }
+
+    // Mirrors `Vec::push` on the track list.
+    pub fn push(&mut self, track: &Audio) {
+        let position = self.keys.len();
+        let key = format!("{}\n{}", track.name, track.author).to_lowercase();
+        for trigram in trigrams(&key) {
+            let postings = self.trigrams.entry(trigram).or_default();
+            if postings.last() != Some(&position) {
+                postings.push(position);
+            }
+        }
+        self.keys.push(key);
+    }
+
+    // Mirrors `Vec::remove` on the track list, every index after `position` moves down by one.
+    pub fn remove(&mut self, position: usize) {
+        if position >= self.keys.len() {
+            return;
         }
+        self.keys.remove(position);
+        self.trigrams.retain(|_, postings| {
+            postings.retain(|index| *index != position);
+            for index in postings.iter_mut() {
+                if *index > position {
+                    *index -= 1;
+                }
+            }
+            !postings.is_empty()
+        });
     }
 
-    matches
+    pub fn search(&self, keyword: &str) -> Vec<usize> {
+        let keyword = keyword.to_lowercase();
+        let query: Vec<Trigram> = trigrams(&keyword).collect();
+
+        // Too short for a trigram, a plain scan over the keys is still cheap.
+        if query.is_empty() {
+            return (0..self.keys.len())
+                .filter(|index| self.keys[*index].contains(&keyword))
+                .collect();
+        }
+
+        // Every match has to contain all trigrams, so the rarest one bounds the candidates.
+        let mut candidates: &[usize] = &[];
+        for trigram in &query {
+            match self.trigrams.get(trigram) {
+                Some(postings) => {
+                    if candidates.is_empty() || postings.len() < candidates.len() {
+                        candidates = postings;
+                    }
+                }
+                None => return Vec::new(),
+            }
+        }
+
+        candidates
+            .iter()
+            .copied()
+            .filter(|index| self.keys[*index].contains(&keyword))
+            .collect()
+    }
+}
+
+fn trigrams(key: &str) -> impl Iterator<Item = Trigram> {
+    let chars: Vec<char> = key.chars().collect();
+    (0..chars.len().saturating_sub(2)).map(move |i| [chars[i], chars[i + 1], chars[i + 2]])
 }
 
 #[cfg(test)]
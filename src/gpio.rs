@@ -15,6 +15,11 @@ const PLAY_PAUSE_PIN: u8 = 13;
 const LEFT_PIN: u8 = 26;
 const RIGHT_PIN: u8 = 5;
 
+// Holding APPEND this long queues the selection instead of picking it, and holding it until
+// PLAY_NEXT_PRESS puts it right after the current track.
+const LONG_PRESS: Duration = Duration::from_millis(600);
+const PLAY_NEXT_PRESS: Duration = Duration::from_millis(1500);
+
 pub(crate) fn setup_gpio() -> Result<Receiver<KeyEvent>, Error> {
     let gpio = Gpio::new()?;
     let (tx, rx) = mpsc::channel();
@@ -74,11 +79,25 @@ pub(crate) fn setup_gpio() -> Result<Receiver<KeyEvent>, Error> {
     )?;
 
     let tx6 = tx.clone();
+    let mut pressed_at = None;
     append_pin.set_async_interrupt(
-        Trigger::FallingEdge,
+        Trigger::Both,
         Some(Duration::from_millis(33)),
-        move |_| {
-            let _ = tx6.send(KeyEvent::new(KeyCode::Char(':'), KeyModifiers::NONE));
+        move |event| match event.trigger {
+            Trigger::FallingEdge => pressed_at = Some(event.timestamp),
+            _ => {
+                if let Some(pressed) = pressed_at.take() {
+                    let held = event.timestamp.saturating_sub(pressed);
+                    let key = if held < LONG_PRESS {
+                        ':'
+                    } else if held < PLAY_NEXT_PRESS {
+                        'a'
+                    } else {
+                        'A'
+                    };
+                    let _ = tx6.send(KeyEvent::new(KeyCode::Char(key), KeyModifiers::NONE));
+                }
+            }
         },
     )?;
 
@@ -0,0 +1,183 @@
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::stats::now;
+
+pub(crate) const FILE_NAME: &str = "history.tsv";
+
+// A listen counts as a play once it reached either limit, set under `[play_threshold]` in
+// daph.toml. Anything shorter counts as a skip.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub(crate) struct PlayThreshold {
+    // Of the track's length.
+    pub percent: f64,
+    pub seconds: u64,
+}
+
+impl Default for PlayThreshold {
+    fn default() -> Self {
+        PlayThreshold {
+            percent: 50.0,
+            seconds: 240,
+        }
+    }
+}
+
+impl PlayThreshold {
+    pub fn is_play(&self, listened: u64, length: u64) -> bool {
+        let share = length as f64 * self.percent / 100.0;
+        listened >= self.seconds || listened as f64 >= share
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Listen {
+    // When the track started, in seconds since the epoch.
+    pub time: u64,
+    pub path: PathBuf,
+    // Seconds that actually played, seeking doesn't count.
+    pub listened: u64,
+    pub is_play: bool,
+}
+
+impl Listen {
+    fn to_line(&self) -> String {
+        let kind = match self.is_play {
+            true => "play",
+            false => "skip",
+        };
+        format!(
+            "{}\t{}\t{}\t{}",
+            self.time,
+            self.listened,
+            kind,
+            self.path.display()
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(4, '\t');
+        let time = fields.next()?.parse().ok()?;
+        let listened = fields.next()?.parse().ok()?;
+        let is_play = match fields.next()? {
+            "play" => true,
+            "skip" => false,
+            _ => return None,
+        };
+        let path = PathBuf::from(fields.next()?);
+        Some(Listen {
+            time,
+            path,
+            listened,
+            is_play,
+        })
+    }
+}
+
+// The track playing right now, it's only written down once it ends.
+#[derive(Debug)]
+struct Listening {
+    path: PathBuf,
+    started: u64,
+    listened: Duration,
+    position: Duration,
+}
+
+// Every play and skip, one line each in history.tsv next to stats.toml.
+#[derive(Debug, Default)]
+pub(crate) struct History {
+    pub path: Option<PathBuf>,
+    pub threshold: PlayThreshold,
+    // Oldest first.
+    pub listens: Vec<Listen>,
+    current: Option<Listening>,
+}
+
+impl History {
+    pub fn load(path: PathBuf, threshold: PlayThreshold) -> Self {
+        let listens = match fs::read_to_string(&path) {
+            Ok(file) => file.lines().filter_map(Listen::from_line).collect(),
+            Err(_) => Vec::new(),
+        };
+        History {
+            path: Some(path),
+            threshold,
+            listens,
+            current: None,
+        }
+    }
+
+    pub fn start(&mut self, path: &Path) {
+        self.current = Some(Listening {
+            path: path.to_path_buf(),
+            started: now(),
+            listened: Duration::ZERO,
+            position: Duration::ZERO,
+        });
+    }
+
+    // Fed with the sink's position while it plays. Only small steps forward are counted,
+    // so jumping around with seek doesn't add to the time listened.
+    pub fn tick(&mut self, position: Duration) {
+        let Some(listening) = self.current.as_mut() else {
+            return;
+        };
+        let step = position.saturating_sub(listening.position);
+        if step < Duration::from_secs(1) {
+            listening.listened += step;
+        }
+        listening.position = position;
+    }
+
+    // Ends the current listen, `length` is the track's length in seconds.
+    pub fn finish(&mut self, length: u64) -> Option<Listen> {
+        let listening = self.current.take()?;
+        let listened = listening.listened.as_secs();
+        let listen = Listen {
+            time: listening.started,
+            path: listening.path,
+            listened,
+            is_play: self.threshold.is_play(listened, length),
+        };
+        self.append(&listen);
+        self.listens.push(listen.clone());
+        Some(listen)
+    }
+
+    fn append(&self, listen: &Listen) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", listen.to_line()));
+        if written.is_err() {
+            log::warn!("Can't write the history: {}", path.display());
+        }
+    }
+
+    // Plays only, newest first and every track once.
+    pub fn recently_played(&self, limit: usize) -> Vec<&Listen> {
+        let mut seen = HashSet::new();
+        self.listens
+            .iter()
+            .rev()
+            .filter(|listen| listen.is_play && seen.insert(&listen.path))
+            .take(limit)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test;
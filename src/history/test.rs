@@ -0,0 +1,65 @@
+use super::*;
+
+#[test]
+fn test_threshold() {
+    let threshold = PlayThreshold::default();
+    assert!(threshold.is_play(100, 200));
+    assert!(!threshold.is_play(99, 200));
+    // Long tracks count after four minutes.
+    assert!(threshold.is_play(240, 3600));
+}
+
+#[test]
+fn test_seeking_does_not_count() {
+    let mut history = History::default();
+    history.start(Path::new("/music/toe/Goodbye.flac"));
+    for step in 0..=100 {
+        history.tick(Duration::from_millis(step * 100));
+    }
+    // Seek ahead by a minute, then keep playing.
+    history.tick(Duration::from_secs(70));
+    history.tick(Duration::from_millis(70_500));
+
+    let listen = history.finish(200).unwrap();
+    assert_eq!(listen.listened, 10);
+    assert!(!listen.is_play);
+    assert_eq!(history.finish(200), None);
+}
+
+#[test]
+fn test_line_round_trip() {
+    let listen = Listen {
+        time: 1_700_000_000,
+        path: PathBuf::from("/music/toe/Two Moons.flac"),
+        listened: 312,
+        is_play: true,
+    };
+    assert_eq!(Listen::from_line(&listen.to_line()), Some(listen));
+    assert_eq!(Listen::from_line("garbage"), None);
+}
+
+#[test]
+fn test_recently_played() {
+    let listen = |time: u64, path: &str, is_play: bool| Listen {
+        time,
+        path: PathBuf::from(path),
+        listened: 0,
+        is_play,
+    };
+    let history = History {
+        listens: vec![
+            listen(1, "a", true),
+            listen(2, "b", true),
+            listen(3, "a", true),
+            listen(4, "c", false),
+        ],
+        ..Default::default()
+    };
+
+    let recent: Vec<u64> = history
+        .recently_played(10)
+        .iter()
+        .map(|listen| listen.time)
+        .collect();
+    assert_eq!(recent, vec![3, 2]);
+}
@@ -0,0 +1,168 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use color_eyre::eyre::{Result, bail};
+use lofty::file::{AudioFile, TaggedFileExt};
+use lofty::read_from_path;
+use lofty::tag::{Accessor, ItemKey};
+use serde::Serialize;
+
+use crate::history::{FILE_NAME, History, Listen, PlayThreshold};
+use crate::utility::config_dir;
+
+// What ListenBrainz wants to know about a track, read from its tags at export time.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Metadata {
+    pub artist: String,
+    pub title: String,
+    pub album: Option<String>,
+    pub track_number: Option<u32>,
+    pub duration_ms: u128,
+    pub recording_mbid: Option<String>,
+    pub release_mbid: Option<String>,
+    pub artist_mbids: Vec<String>,
+}
+
+impl Metadata {
+    fn read(path: &Path) -> Option<Self> {
+        let tagged_file = read_from_path(path).ok()?;
+        let tag = tagged_file.primary_tag()?;
+        let text = |key: ItemKey| tag.get_string(&key).map(String::from);
+        Some(Metadata {
+            artist: tag.artist()?.to_string(),
+            title: tag.title()?.to_string(),
+            album: tag.album().map(|album| album.to_string()),
+            track_number: tag.track(),
+            duration_ms: tagged_file.properties().duration().as_millis(),
+            recording_mbid: text(ItemKey::MusicBrainzRecordingId),
+            release_mbid: text(ItemKey::MusicBrainzReleaseId),
+            // Taggers put several artists in one field or in many, depending on the format.
+            artist_mbids: tag
+                .get_strings(&ItemKey::MusicBrainzArtistId)
+                .flat_map(|ids| ids.split(['/', ';']))
+                .map(|id| id.trim().to_string())
+                .filter(|id| !id.is_empty())
+                .collect(),
+        })
+    }
+}
+
+// `daph export-listens [--since <unix time | YYYY-MM-DD>]`, writes one listen per line to
+// stdout in the format ListenBrainz takes for imports.
+pub(crate) fn export_listens(args: &[String]) -> Result<()> {
+    let since = match args {
+        [] => 0,
+        [flag, value] if flag == "--since" => match parse_since(value) {
+            Some(since) => since,
+            None => bail!("--since takes a unix timestamp or a YYYY-MM-DD date, got: {value}"),
+        },
+        _ => bail!("Usage: daph export-listens [--since <unix time | YYYY-MM-DD>]"),
+    };
+    let Some(dir) = config_dir() else {
+        bail!("Can't find the config directory.");
+    };
+
+    let history = History::load(dir.join(FILE_NAME), PlayThreshold::default());
+    let mut out = io::stdout().lock();
+    for listen in history
+        .listens
+        .iter()
+        .filter(|listen| listen.is_play && listen.time >= since)
+    {
+        match Metadata::read(&listen.path) {
+            Some(metadata) => writeln!(out, "{}", listen_json(listen, &metadata)?)?,
+            None => eprintln!("Skipping, no artist or title: {}", listen.path.display()),
+        }
+    }
+    Ok(())
+}
+
+fn parse_since(value: &str) -> Option<u64> {
+    if let Ok(timestamp) = value.parse() {
+        return Some(timestamp);
+    }
+    let mut parts = value.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+    let seconds = days_from_civil(year, month, day) * 24 * 60 * 60;
+    u64::try_from(seconds).ok()
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    let is_leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    match month {
+        2 if is_leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 for a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+// The listen as ListenBrainz takes it, fields without a value are left out.
+#[derive(Serialize)]
+struct ListenJson<'a> {
+    listened_at: u64,
+    track_metadata: TrackMetadata<'a>,
+}
+
+#[derive(Serialize)]
+struct TrackMetadata<'a> {
+    artist_name: &'a str,
+    track_name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    release_name: Option<&'a str>,
+    additional_info: AdditionalInfo<'a>,
+}
+
+#[derive(Serialize)]
+struct AdditionalInfo<'a> {
+    media_player: &'a str,
+    submission_client: &'a str,
+    submission_client_version: &'a str,
+    duration_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tracknumber: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recording_mbid: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    release_mbid: Option<&'a str>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    artist_mbids: &'a [String],
+}
+
+pub(crate) fn listen_json(listen: &Listen, metadata: &Metadata) -> serde_json::Result<String> {
+    serde_json::to_string(&ListenJson {
+        listened_at: listen.time,
+        track_metadata: TrackMetadata {
+            artist_name: &metadata.artist,
+            track_name: &metadata.title,
+            release_name: metadata.album.as_deref(),
+            additional_info: AdditionalInfo {
+                media_player: "daph",
+                submission_client: "daph",
+                submission_client_version: env!("CARGO_PKG_VERSION"),
+                duration_ms: metadata.duration_ms,
+                tracknumber: metadata.track_number,
+                recording_mbid: metadata.recording_mbid.as_deref(),
+                release_mbid: metadata.release_mbid.as_deref(),
+                artist_mbids: &metadata.artist_mbids,
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+
+use super::*;
+
+#[test]
+fn test_parse_since() {
+    assert_eq!(parse_since("1700000000"), Some(1_700_000_000));
+    assert_eq!(parse_since("2023-11-14"), Some(1_699_920_000));
+    assert_eq!(parse_since("1970-01-01"), Some(0));
+    assert_eq!(parse_since("2023-13-01"), None);
+    assert_eq!(parse_since("2024-02-31"), None);
+    assert_eq!(parse_since("2023-02-29"), None);
+    assert_eq!(parse_since("2024-02-29"), Some(1_709_164_800));
+    assert_eq!(parse_since("yesterday"), None);
+}
+
+#[test]
+fn test_listen_json() {
+    let listen = Listen {
+        time: 1_700_000_000,
+        path: PathBuf::from("/music/toe/Goodbye.flac"),
+        listened: 287,
+        is_play: true,
+    };
+    let metadata = Metadata {
+        artist: String::from("toe"),
+        title: String::from("Say \"Goodbye\""),
+        track_number: Some(3),
+        duration_ms: 287_000,
+        artist_mbids: vec![String::from("a1"), String::from("b2")],
+        ..Default::default()
+    };
+    assert_eq!(
+        listen_json(&listen, &metadata).unwrap(),
+        concat!(
+            r#"{"listened_at":1700000000,"track_metadata":{"artist_name":"toe","#,
+            r#""track_name":"Say \"Goodbye\"","additional_info":{"media_player":"daph","#,
+            r#""submission_client":"daph","submission_client_version":""#,
+            env!("CARGO_PKG_VERSION"),
+            r#"","#,
+            r#""duration_ms":287000,"tracknumber":3,"artist_mbids":["a1","b2"]}}}"#
+        )
+    );
+}
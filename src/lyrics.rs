@@ -0,0 +1,284 @@
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use lofty::config::ParseOptions;
+use lofty::file::{AudioFile, TaggedFileExt};
+use lofty::id3::v2::{Frame, FrameId, SynchronizedTextFrame, TimestampFormat};
+use lofty::mpeg::MpegFile;
+use lofty::read_from_path;
+use lofty::tag::ItemKey;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Lyrics {
+    // Sorted by when each line starts.
+    Synced(Vec<(Duration, String)>),
+    Unsynced(Vec<String>),
+}
+
+impl Lyrics {
+    // A .lrc file next to the track wins over the tags. Embedded SYLT frames come before
+    // USLT and LYRICS, which often hold LRC text themselves.
+    pub fn load(path: &Path) -> Option<Self> {
+        sidecar(path)
+            .or_else(|| synchronized(path))
+            .or_else(|| embedded(path))
+            .filter(|lyrics| !lyrics.is_empty())
+    }
+
+    // LRC when there are time tags, plain lines otherwise.
+    pub fn parse(text: &str) -> Self {
+        let mut offset: i64 = 0;
+        let mut synced = Vec::new();
+        for line in text.lines() {
+            let line = line.trim().trim_start_matches('\u{feff}');
+            let (times, words) = time_tags(line);
+            match times.is_empty() {
+                true => {
+                    if let Some(value) = tag_value(line, "offset") {
+                        offset = value.parse().unwrap_or(0);
+                    }
+                }
+                false => synced.extend(times.into_iter().map(|time| (time, strip_words(words)))),
+            }
+        }
+
+        if synced.is_empty() {
+            return Lyrics::Unsynced(text.lines().map(|line| line.trim().to_string()).collect());
+        }
+        // A positive offset shows the lines sooner.
+        for (time, _) in &mut synced {
+            *time = match offset >= 0 {
+                true => time.saturating_sub(Duration::from_millis(offset as u64)),
+                false => *time + Duration::from_millis(offset.unsigned_abs()),
+            };
+        }
+        synced.sort_by_key(|(time, _)| *time);
+        Lyrics::Synced(synced)
+    }
+
+    pub fn lines(&self) -> Vec<&str> {
+        match self {
+            Lyrics::Synced(lines) => lines.iter().map(|(_, line)| line.as_str()).collect(),
+            Lyrics::Unsynced(lines) => lines.iter().map(String::as_str).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines().iter().all(|line| line.is_empty())
+    }
+
+    // The last line that started at or before `position`, None before the first one and
+    // for unsynced lyrics.
+    pub fn current_line(&self, position: Duration) -> Option<usize> {
+        let Lyrics::Synced(lines) = self else {
+            return None;
+        };
+        lines
+            .partition_point(|(time, _)| *time <= position)
+            .checked_sub(1)
+    }
+}
+
+// The leading [mm:ss.xx] tags of a line and what's left after them.
+fn time_tags(line: &str) -> (Vec<Duration>, &str) {
+    let mut times = Vec::new();
+    let mut rest = line;
+    while let Some(tag) = rest.strip_prefix('[') {
+        let Some(end) = tag.find(']') else {
+            break;
+        };
+        let Some(time) = parse_time(&tag[..end]) else {
+            break;
+        };
+        times.push(time);
+        rest = &tag[end + 1..];
+    }
+    (times, rest)
+}
+
+// mm:ss, mm:ss.xx or mm:ss:xx, the minutes may go past 59.
+fn parse_time(tag: &str) -> Option<Duration> {
+    let (minutes, rest) = tag.split_once(':')?;
+    let minutes: u64 = minutes.trim().parse().ok()?;
+    let (seconds, fraction) = match rest.split_once(['.', ':']) {
+        Some((seconds, fraction)) => (seconds, fraction),
+        None => (rest, ""),
+    };
+    let seconds: u64 = seconds.trim().parse().ok()?;
+    let millis = match fraction {
+        "" => 0,
+        fraction if fraction.chars().all(|c| c.is_ascii_digit()) => {
+            // ".5" is half a second, ".05" and ".050" are the same.
+            let digits = &fraction[..fraction.len().min(3)];
+            digits.parse::<u64>().ok()? * 10u64.pow(3 - digits.len() as u32)
+        }
+        _ => return None,
+    };
+    Some(Duration::from_millis(
+        (minutes * 60 + seconds) * 1000 + millis,
+    ))
+}
+
+// Metadata like [offset:+250] or [ar:Artist].
+fn tag_value<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let (key, value) = line.strip_prefix('[')?.strip_suffix(']')?.split_once(':')?;
+    match key.trim().eq_ignore_ascii_case(name) {
+        true => Some(value.trim()),
+        false => None,
+    }
+}
+
+// Enhanced LRC times single words with <mm:ss.xx>, only whole lines are highlighted here.
+fn strip_words(line: &str) -> String {
+    let mut text = String::new();
+    let mut rest = line;
+    while let Some(start) = rest.find('<') {
+        let end = rest[start..].find('>').map(|end| start + end);
+        match end.filter(|end| parse_time(&rest[start + 1..*end]).is_some()) {
+            Some(end) => {
+                text.push_str(&rest[..start]);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                text.push_str(&rest[..=start]);
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    text.push_str(rest);
+    text.trim().to_string()
+}
+
+fn sidecar(path: &Path) -> Option<Lyrics> {
+    let lrc: PathBuf = path.with_extension("lrc");
+    let bytes = fs::read(lrc).ok()?;
+    Some(Lyrics::parse(&String::from_utf8_lossy(&bytes)))
+}
+
+// USLT in ID3v2, LYRICS in Vorbis comments and ©lyr in MP4 all end up under the same key.
+fn embedded(path: &Path) -> Option<Lyrics> {
+    let tagged_file = read_from_path(path).ok()?;
+    let text = tagged_file.primary_tag()?.get_string(&ItemKey::Lyrics)?;
+    Some(Lyrics::parse(text))
+}
+
+// SYLT frames only exist in ID3v2, and lofty keeps them out of its generic tag. Other formats
+// are left alone, lofty would search them for an MPEG frame to the end.
+fn synchronized(path: &Path) -> Option<Lyrics> {
+    if !path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("mp3"))
+    {
+        return None;
+    }
+    let mut file = BufReader::new(File::open(path).ok()?);
+    let mpeg = MpegFile::read_from(&mut file, ParseOptions::new()).ok()?;
+    let Frame::Binary(binary) = mpeg.id3v2()?.get(&FrameId::new("SYLT").ok()?)? else {
+        return None;
+    };
+    let frame = SynchronizedTextFrame::parse(&binary.data, binary.flags()).ok()?;
+    // Timestamps in MPEG frames would need the frame rate, those are rare enough to leave out.
+    if frame.timestamp_format != TimestampFormat::MS {
+        return None;
+    }
+    Some(Lyrics::Synced(sylt_lines(&frame.content)))
+}
+
+// Taggers write either one entry per line or one per syllable, where a newline at the start
+// of an entry begins the next line.
+pub(crate) fn sylt_lines(content: &[(u32, String)]) -> Vec<(Duration, String)> {
+    let by_syllable = content
+        .iter()
+        .any(|(_, text)| text.starts_with(['\n', '\r']));
+    let mut lines: Vec<(Duration, String)> = Vec::new();
+    for (time, text) in content {
+        let starts_line = !by_syllable || text.starts_with(['\n', '\r']) || lines.is_empty();
+        let text = text.trim_start_matches(['\n', '\r']);
+        match (starts_line, lines.last_mut()) {
+            (false, Some((_, line))) => line.push_str(text),
+            _ => lines.push((Duration::from_millis(*time as u64), text.to_string())),
+        }
+    }
+    for (_, line) in &mut lines {
+        *line = line.trim().to_string();
+    }
+    lines.sort_by_key(|(time, _)| *time);
+    lines
+}
+
+type Loaded = (PathBuf, Option<Lyrics>);
+
+// Only the lyrics of the track on screen are kept, they are read again when it changes. Going
+// through the tags of a large file takes a while, so that's left to a background thread.
+#[derive(Default)]
+pub(crate) struct LyricsCache {
+    entry: Option<Loaded>,
+    // The track whose lyrics are being read.
+    pending: Option<PathBuf>,
+    worker: Option<(Sender<PathBuf>, Receiver<Loaded>)>,
+}
+
+impl LyricsCache {
+    // Has the lyrics read, unless they're the ones kept or already on their way.
+    pub fn request(&mut self, path: &Path) {
+        let is_loaded = self
+            .entry
+            .as_ref()
+            .is_some_and(|(loaded, _)| loaded == path);
+        if is_loaded || self.pending.as_deref() == Some(path) {
+            return;
+        }
+        self.pending = Some(path.to_path_buf());
+        let (tx, _) = self.worker.get_or_insert_with(spawn_worker);
+        tx.send(path.to_path_buf()).unwrap_or(());
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&Lyrics> {
+        match &self.entry {
+            Some((loaded, lyrics)) if loaded == path => lyrics.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    // Picks up whatever the background thread finished, called from the main loop. Only the
+    // last track asked for is kept.
+    pub fn receive(&mut self) {
+        let Some((_, rx)) = &self.worker else {
+            return;
+        };
+        let loaded: Vec<Loaded> = rx.try_iter().collect();
+        for (path, lyrics) in loaded {
+            if self.pending.as_ref() == Some(&path) {
+                self.pending = None;
+                self.entry = Some((path, lyrics));
+            }
+        }
+    }
+}
+
+fn spawn_worker() -> (Sender<PathBuf>, Receiver<Loaded>) {
+    let (request_tx, request_rx) = mpsc::channel::<PathBuf>();
+    let (loaded_tx, loaded_rx) = mpsc::channel::<Loaded>();
+    let _ = thread::Builder::new()
+        .name("lyrics".to_string())
+        .spawn(move || {
+            for path in request_rx {
+                let lyrics = Lyrics::load(&path);
+                if loaded_tx.send((path, lyrics)).is_err() {
+                    break;
+                }
+            }
+        });
+    (request_tx, loaded_rx)
+}
+
+#[cfg(test)]
+mod test;
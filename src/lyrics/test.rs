@@ -0,0 +1,56 @@
+use super::*;
+
+#[test]
+fn test_parse_lrc() {
+    let text = "[ar:Someone]\n\
+        [offset:+500]\n\
+        [00:12.00]First line\n\
+        [00:05.5][01:02:30]Chorus\n\
+        [00:20.123]<00:20.12>Word <00:21.00>by word\n\
+        Stray line";
+    let lyrics = Lyrics::parse(text);
+    let ms = Duration::from_millis;
+    assert_eq!(
+        lyrics,
+        Lyrics::Synced(vec![
+            (ms(5_000), String::from("Chorus")),
+            (ms(11_500), String::from("First line")),
+            (ms(19_623), String::from("Word by word")),
+            (ms(61_800), String::from("Chorus")),
+        ])
+    );
+
+    assert_eq!(lyrics.current_line(ms(1_000)), None);
+    assert_eq!(lyrics.current_line(ms(5_000)), Some(0));
+    assert_eq!(lyrics.current_line(ms(19_000)), Some(1));
+    assert_eq!(lyrics.current_line(ms(600_000)), Some(3));
+}
+
+#[test]
+fn test_parse_unsynced() {
+    let lyrics = Lyrics::parse("Just words\n[not a time] either\n");
+    assert_eq!(
+        lyrics,
+        Lyrics::Unsynced(vec![
+            String::from("Just words"),
+            String::from("[not a time] either"),
+        ])
+    );
+    assert_eq!(lyrics.current_line(Duration::from_secs(10)), None);
+}
+
+#[test]
+fn test_sylt_lines() {
+    let syllables = [
+        (1000, String::from("Hel")),
+        (1200, String::from("lo")),
+        (2000, String::from("\nworld")),
+    ];
+    assert_eq!(
+        sylt_lines(&syllables),
+        vec![
+            (Duration::from_millis(1000), String::from("Hello")),
+            (Duration::from_millis(2000), String::from("world")),
+        ]
+    );
+}
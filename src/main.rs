@@ -1,42 +1,92 @@
+use crate::button_handler::handle_browse;
 use crate::button_handler::handle_choosing;
 use crate::button_handler::handle_config;
+use crate::button_handler::handle_history;
+use crate::button_handler::handle_lyrics;
 use crate::button_handler::handle_playback;
+use crate::button_handler::handle_playlists;
+use crate::button_handler::handle_queue;
 use crate::button_handler::handle_search;
 use crate::gpio::setup_gpio;
 use crate::state::Configure;
 use crate::state::PlayerState;
+use crate::utility::advance;
+use crate::utility::finish_listening;
 use crate::utility::play_new_track;
 use crate::view::render;
 use color_eyre::eyre::Result;
 use crossterm::event::{self, Event, KeyEvent};
+use history::PlayThreshold;
+use order::SortPreset;
 use playback::SinkState;
+use playlist::smart::SmartPlaylist;
 use ratatui::DefaultTerminal;
 use serde::Deserialize;
+use stats::Weights;
 use std::path::PathBuf;
 use std::result::Result::Ok;
 use std::sync::mpsc::Receiver;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use theme::CustomTheme;
+mod browse;
 mod button_handler;
+mod cover;
 mod fuzzy_search;
 mod gpio;
+mod history;
+mod listenbrainz;
+mod lyrics;
+mod menu;
 mod order;
 mod playback;
+mod playlist;
+mod queue;
+mod scrobbler;
+mod spectrum;
 mod state;
+mod stats;
+mod theme;
 mod utility;
 mod view;
+mod waveform;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Default)]
 struct Config {
     path: PathBuf,
     seek_distance: usize,
+    #[serde(default)]
+    weights: Weights,
+    #[serde(default)]
+    sort: Vec<SortPreset>,
+    // Also store ratings in the files' tags, not only in stats.toml.
+    #[serde(default)]
+    write_ratings: bool,
+    #[serde(default)]
+    play_threshold: PlayThreshold,
+    #[serde(default)]
+    smart_playlist: Vec<SmartPlaylist>,
+    // Where completed plays are logged for scrobbling, the root of the library by default.
+    scrobble_log: Option<PathBuf>,
+    // The name of a built-in or custom theme, "default" when left out.
+    theme: Option<String>,
+    #[serde(default)]
+    custom_theme: Vec<CustomTheme>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 struct Audio {
     is_playing: bool,
     name: String,
     author: String,
+    album: String,
+    genre: String,
+    year: Option<u32>,
+    track_number: Option<u32>,
     length: u64,
+    // Modification time of the file, in seconds since the epoch.
+    added: u64,
+    // The MusicBrainz recording id, when the file was tagged with one.
+    musicbrainz_id: Option<String>,
     path: PathBuf,
 }
 
@@ -47,6 +97,8 @@ pub(crate) enum Command {
     Forward(usize, usize),
     Backward(usize),
     Volume(f32),
+    // Whether the playback thread should analyze what it plays.
+    Visualize(bool),
     _Next(PathBuf, i32),
     _Previous(PathBuf, i32),
     _Append(PathBuf, i32),
@@ -61,6 +113,14 @@ enum Action {
 fn main() -> Result<()> {
     env_logger::init();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(command) = args.first() {
+        match command.as_str() {
+            "export-listens" => return listenbrainz::export_listens(&args[1..]),
+            _ => color_eyre::eyre::bail!("Unknown command: {command}"),
+        }
+    }
+
     let mut state = if let Some(path) = home::home_dir() {
         let config_path = path.join(".config").join("daph.toml");
         if config_path.exists() {
@@ -100,83 +160,124 @@ fn run(
             terminal.draw(|f| render(f, state, &sink))?;
 
             // Handle GPIO button input
-            if let Ok(rx) = gpio_rx {
-                if let Ok(key) = rx.try_recv() {
-                    if state.is_searching {
-                        match handle_search(key, state) {
-                            Action::Escape => state.is_searching = false,
-                            Action::Submit => {}
-                            Action::None => {}
-                        }
-                    } else if state.is_configuring {
-                        match handle_config(key, state) {
-                            Action::Escape => state.is_configuring = false,
-                            Action::Submit => state.is_configuring = false,
-                            Action::None => {}
-                        }
-                    } else if state.is_choosing {
-                        match handle_choosing(key, state) {
-                            Action::Escape => state.is_choosing = false,
-                            Action::Submit => {}
-                            Action::None => {}
-                        }
-                    } else {
-                        match handle_playback(key, state) {
-                            Action::Escape => break,
-                            Action::Submit => {}
-                            Action::None => {}
-                        }
-                    }
-                }
+            if let Ok(rx) = gpio_rx
+                && let Ok(key) = rx.try_recv()
+                && handle_key(key, state, true)
+            {
+                break;
             }
 
             // For testing purpose, keep the keyboard input.
-            if event::poll(std::time::Duration::from_millis(16))? {
-                if let Event::Key(key) = event::read()? {
-                    if state.is_searching {
-                        match handle_search(key, state) {
-                            Action::Escape => state.is_searching = false,
-                            Action::Submit => {}
-                            Action::None => {}
-                        }
-                    } else if state.is_configuring {
-                        match handle_config(key, state) {
-                            Action::Escape => state.is_configuring = false,
-                            Action::Submit => {}
-                            Action::None => {}
-                        }
-                    } else {
-                        match handle_playback(key, state) {
-                            Action::Escape => break,
-                            Action::Submit => {}
-                            Action::None => {}
-                        }
-                    }
-                }
+            if event::poll(std::time::Duration::from_millis(16))?
+                && let Event::Key(key) = event::read()?
+                && handle_key(key, state, false)
+            {
+                break;
             }
 
+            if sink.is_playing {
+                state.history.tick(sink.position);
+            }
+            state.waveforms.receive();
+            state.covers.borrow_mut().receive();
+            state.lyrics.borrow_mut().receive();
+            state.stats.save_if_due();
+
             // Auto-Queue
             if sink.current_track_finished {
-                if let Some(mut index) = state.current_track_index {
-                    state.tracks[index].is_playing = false;
-                    index = (index + 1) % state.number_of_tracks;
-                    play_new_track(index, state);
-                }
+                advance(state);
             }
 
             // If we assume two threads are perfectly in sync(probably impossible),
             // in total, one iteration should take 49ms when no button is pressed.
             // 4s / 49ms = ~82
             state.iteration_count += 1;
-            if state.iteration_count % 82 == 0 {
+            if state.iteration_count.is_multiple_of(82) {
                 state.is_adjusting = false;
                 if !sink.is_empty {
                     state.is_choosing = false;
+                    state.is_browsing = false;
+                    state.is_viewing_history = false;
+                    if !state.is_naming_playlist {
+                        state.is_viewing_playlists = false;
+                    }
+                    if !state.is_moving_entry {
+                        state.is_viewing_queue = false;
+                    }
                 }
                 state.is_configuring = false;
                 state.iteration_count = 0;
             }
         }
     }
+    finish_listening(state);
+    state.stats.save();
     Ok(())
 }
+
+// Returns true when the player should quit. The keyboard is only there for testing and keeps
+// its own behaviour: it skips the list section's keys and leaves the config screen open.
+fn handle_key(key: KeyEvent, state: &mut PlayerState, is_button: bool) -> bool {
+    let selected = (state.table_state.selected(), state.search_state.selected());
+    if state.is_searching {
+        match handle_search(key, state) {
+            Action::Escape => state.is_searching = false,
+            Action::Submit => {}
+            Action::None => {}
+        }
+    } else if state.is_configuring {
+        match handle_config(key, state) {
+            Action::Escape => state.is_configuring = false,
+            Action::Submit if is_button => state.is_configuring = false,
+            Action::Submit => {}
+            Action::None => {}
+        }
+    } else if state.is_viewing_queue {
+        match handle_queue(key, state) {
+            Action::Escape => state.is_viewing_queue = false,
+            Action::Submit => state.is_viewing_queue = false,
+            Action::None => {}
+        }
+    } else if state.is_viewing_history {
+        match handle_history(key, state) {
+            Action::Escape => state.is_viewing_history = false,
+            Action::Submit => state.is_viewing_history = false,
+            Action::None => {}
+        }
+    } else if state.is_viewing_playlists {
+        match handle_playlists(key, state) {
+            Action::Escape => state.is_viewing_playlists = false,
+            Action::Submit => state.is_viewing_playlists = false,
+            Action::None => {}
+        }
+    } else if state.is_browsing {
+        match handle_browse(key, state) {
+            Action::Escape => state.is_browsing = false,
+            Action::Submit => state.is_browsing = false,
+            Action::None => {}
+        }
+    } else if state.is_choosing && is_button {
+        match handle_choosing(key, state) {
+            Action::Escape => state.is_choosing = false,
+            Action::Submit => {}
+            Action::None => {}
+        }
+    } else if state.is_viewing_lyrics {
+        match handle_lyrics(key, state) {
+            Action::Escape => state.is_viewing_lyrics = false,
+            Action::Submit => {}
+            Action::None => {}
+        }
+    } else {
+        match handle_playback(key, state) {
+            Action::Escape => return true,
+            Action::Submit => {}
+            Action::None => {}
+        }
+    }
+    // A newly selected row scrolls its title from the start.
+    if selected != (state.table_state.selected(), state.search_state.selected()) {
+        state.marquee_clock = Instant::now();
+    }
+    false
+}
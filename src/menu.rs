@@ -0,0 +1,88 @@
+use std::fmt::Display;
+
+use crate::browse::BrowseRoot;
+use crate::order::{Order, SortPreset};
+use crate::queue::Repeat;
+use crate::theme::Theme;
+
+// Everything listed on the config screen, top to bottom.
+pub(crate) enum ConfigOption {
+    Order(Order),
+    Repeat(Repeat),
+    // Both act on the highlighted row while the table is showing and on the playing track
+    // otherwise, the buttons have no keys to spare for them.
+    Rate,
+    Favourite,
+    Browse(BrowseRoot),
+    Queue,
+    History,
+    Playlists,
+    // Puts the highlighted row or the playing track, as with Rate, in the last playlist that
+    // was created or opened.
+    AddToPlaylist,
+    Lyrics,
+    // Steps through the visualizers the way v does.
+    Visualizer,
+    // Counts the progress label down instead of up, or back again, the way t does.
+    RemainingTime,
+    Theme(String),
+}
+
+impl Display for ConfigOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigOption::Order(order) => write!(f, "{order}"),
+            ConfigOption::Repeat(repeat) => write!(f, "{repeat}"),
+            ConfigOption::Rate => write!(f, "Rate track"),
+            ConfigOption::Favourite => write!(f, "Favourite track"),
+            ConfigOption::Browse(root) => write!(f, "Browse {root}"),
+            ConfigOption::Queue => write!(f, "Queue"),
+            ConfigOption::History => write!(f, "Recently played"),
+            ConfigOption::Playlists => write!(f, "Playlists"),
+            ConfigOption::AddToPlaylist => write!(f, "Add track to playlist"),
+            ConfigOption::Lyrics => write!(f, "Lyrics"),
+            ConfigOption::Visualizer => write!(f, "Next visualizer"),
+            ConfigOption::RemainingTime => write!(f, "Remaining time"),
+            ConfigOption::Theme(name) => write!(f, "Theme {name}"),
+        }
+    }
+}
+
+pub(crate) fn config_options(presets: &[SortPreset], themes: &[Theme]) -> Vec<ConfigOption> {
+    let mut options = vec![
+        ConfigOption::Order(Order::Shuffle),
+        ConfigOption::Order(Order::SmartShuffle),
+        ConfigOption::Order(Order::AlbumShuffle),
+        ConfigOption::Order(Order::WeightedShuffle),
+    ];
+    options.extend(
+        presets
+            .iter()
+            .map(|preset| ConfigOption::Order(Order::Sort(preset.clone()))),
+    );
+    options.extend([
+        ConfigOption::Repeat(Repeat::Off),
+        ConfigOption::Repeat(Repeat::All),
+        ConfigOption::Repeat(Repeat::One),
+        ConfigOption::Repeat(Repeat::StopAfterCurrent),
+        ConfigOption::Rate,
+        ConfigOption::Favourite,
+        ConfigOption::Browse(BrowseRoot::Artists),
+        ConfigOption::Browse(BrowseRoot::Genres),
+        ConfigOption::Browse(BrowseRoot::Years),
+        ConfigOption::Browse(BrowseRoot::Folders),
+        ConfigOption::Queue,
+        ConfigOption::History,
+        ConfigOption::Playlists,
+        ConfigOption::AddToPlaylist,
+        ConfigOption::Lyrics,
+        ConfigOption::Visualizer,
+        ConfigOption::RemainingTime,
+    ]);
+    options.extend(
+        themes
+            .iter()
+            .map(|theme| ConfigOption::Theme(theme.name.clone())),
+    );
+    options
+}
@@ -1,16 +1,24 @@
+use std::cmp::Ordering;
 use std::fmt::Display;
 
-// TODO: Anything involving Order is just horrible code. Refactor.
+use serde::Deserialize;
+
+use crate::Audio;
+use crate::stats::Stats;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Order {
     Shuffle,
-    Album,
-    Artist,
-    Track,
+    SmartShuffle,
+    AlbumShuffle,
+    WeightedShuffle,
+    Sort(SortPreset),
 }
 
-impl PartialEq for Order {
-    fn eq(&self, other: &Self) -> bool {
-        self.to_string() == other.to_string()
+impl Order {
+    // Shuffles rearrange the queue only, the others sort the library table.
+    pub fn is_shuffle(&self) -> bool {
+        !matches!(self, Order::Sort(_))
     }
 }
 
@@ -18,22 +26,226 @@ impl Display for Order {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Order::Shuffle => write!(f, "Shuffle"),
-            Order::Album => write!(f, "Album"),
-            Order::Artist => write!(f, "Artist"),
-            Order::Track => write!(f, "Track"),
+            Order::SmartShuffle => write!(f, "Smart shuffle"),
+            Order::AlbumShuffle => write!(f, "Album shuffle"),
+            Order::WeightedShuffle => write!(f, "Weighted shuffle"),
+            Order::Sort(preset) => write!(f, "{}", preset.name),
         }
     }
 }
 
-impl Iterator for Order {
-    type Item = Order;
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SortField {
+    Title,
+    Artist,
+    Album,
+    Genre,
+    Year,
+    Track,
+    Length,
+    Added,
+    PlayCount,
+    SkipCount,
+    LastPlayed,
+    Rating,
+    Favourite,
+    Path,
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        match self {
-            Order::Shuffle => Some(Order::Album),
-            Order::Album => Some(Order::Artist),
-            Order::Artist => Some(Order::Track),
-            Order::Track => Some(Order::Shuffle),
+impl SortField {
+    // The names used in daph.toml, for sort keys and smart playlist rules alike.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let field = match name {
+            "title" => SortField::Title,
+            "artist" => SortField::Artist,
+            "album" => SortField::Album,
+            "genre" => SortField::Genre,
+            "year" => SortField::Year,
+            "track" => SortField::Track,
+            "length" => SortField::Length,
+            "added" => SortField::Added,
+            "play_count" => SortField::PlayCount,
+            "skip_count" => SortField::SkipCount,
+            "last_played" => SortField::LastPlayed,
+            "rating" => SortField::Rating,
+            "favourite" => SortField::Favourite,
+            "path" => SortField::Path,
+            _ => return None,
+        };
+        Some(field)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Direction {
+    Ascending,
+    Descending,
+}
+
+// Written as the field name in daph.toml, with a leading '-' for descending, e.g. "-year".
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub(crate) struct SortKey {
+    pub field: SortField,
+    pub direction: Direction,
+}
+
+impl TryFrom<String> for SortKey {
+    type Error = String;
+
+    fn try_from(key: String) -> Result<Self, Self::Error> {
+        let (direction, name) = match key.strip_prefix('-') {
+            Some(name) => (Direction::Descending, name),
+            None => (Direction::Ascending, key.as_str()),
+        };
+        match SortField::from_name(name) {
+            Some(field) => Ok(SortKey { field, direction }),
+            None => Err(format!("Unknown sort field: {name}")),
+        }
+    }
+}
+
+// A named list of sort keys, the later keys only break ties of the earlier ones.
+//
+// [[sort]]
+// name = "Newest first"
+// keys = ["-year", "album", "track"]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SortPreset {
+    pub name: String,
+    pub keys: Vec<SortKey>,
+}
+
+impl SortPreset {
+    fn new(name: &str, keys: &[&str]) -> Self {
+        SortPreset {
+            name: String::from(name),
+            keys: keys
+                .iter()
+                .filter_map(|key| SortKey::try_from(key.to_string()).ok())
+                .collect(),
+        }
+    }
+
+    // What the library table is sorted by on startup.
+    pub fn artist() -> Self {
+        SortPreset::new("Artist", &["artist", "title"])
+    }
+
+    pub fn compare(&self, a: &Audio, b: &Audio, stats: &Stats) -> Ordering {
+        compare_keys(&self.keys, a, b, stats)
+    }
+}
+
+pub(crate) fn compare_keys(keys: &[SortKey], a: &Audio, b: &Audio, stats: &Stats) -> Ordering {
+    keys.iter()
+        .map(|key| compare_field(key, a, b, stats))
+        .find(|ordering| *ordering != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+// The presets that are always there, the ones from daph.toml come after them.
+pub(crate) fn builtin_presets() -> Vec<SortPreset> {
+    vec![
+        SortPreset::new("Album", &["album", "track", "title"]),
+        SortPreset::artist(),
+        SortPreset::new("Track", &["title"]),
+    ]
+}
+
+fn compare_field(key: &SortKey, a: &Audio, b: &Audio, stats: &Stats) -> Ordering {
+    let ordering = match key.field {
+        SortField::Year => return missing_last(a.year, b.year, key.direction),
+        SortField::Track => return missing_last(a.track_number, b.track_number, key.direction),
+        SortField::Title => natural_cmp(&a.name, &b.name),
+        SortField::Artist => natural_cmp(&a.author, &b.author),
+        SortField::Album => natural_cmp(&a.album, &b.album),
+        SortField::Genre => natural_cmp(&a.genre, &b.genre),
+        SortField::Length => a.length.cmp(&b.length),
+        SortField::Added => a.added.cmp(&b.added),
+        SortField::PlayCount => stats
+            .get(&a.path)
+            .play_count
+            .cmp(&stats.get(&b.path).play_count),
+        SortField::SkipCount => stats
+            .get(&a.path)
+            .skip_count
+            .cmp(&stats.get(&b.path).skip_count),
+        SortField::LastPlayed => {
+            let (a, b) = (
+                stats.get(&a.path).last_played,
+                stats.get(&b.path).last_played,
+            );
+            return missing_last(a, b, key.direction);
+        }
+        SortField::Rating => {
+            let (a, b) = (stats.get(&a.path).rating, stats.get(&b.path).rating);
+            return missing_last(a, b, key.direction);
         }
+        SortField::Favourite => stats
+            .get(&a.path)
+            .favourite
+            .cmp(&stats.get(&b.path).favourite),
+        SortField::Path => a.path.cmp(&b.path),
+    };
+    direct(ordering, key.direction)
+}
+
+fn direct(ordering: Ordering, direction: Direction) -> Ordering {
+    match direction {
+        Direction::Ascending => ordering,
+        Direction::Descending => ordering.reverse(),
+    }
+}
+
+// Tracks without the tag end up at the bottom in either direction.
+fn missing_last<T: Ord>(a: Option<T>, b: Option<T>, direction: Direction) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => direct(a.cmp(&b), direction),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+// Case-insensitive, and runs of digits compare by value so "Track 2" comes before "Track 10".
+pub(crate) fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = take_number(&mut a);
+                let y = take_number(&mut b);
+                // Compare by length first, the numbers may not fit any integer.
+                let ordering = x.len().cmp(&y.len()).then_with(|| x.cmp(&y));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+fn take_number(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut number = String::new();
+    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+        number.push(digit);
     }
+    let trimmed = number.trim_start_matches('0');
+    trimmed.to_string()
 }
+
+#[cfg(test)]
+mod test;
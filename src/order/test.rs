@@ -0,0 +1,56 @@
+use super::*;
+
+fn track(name: &str, album: &str, year: Option<u32>) -> Audio {
+    Audio {
+        name: String::from(name),
+        album: String::from(album),
+        year,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_natural_cmp() {
+    assert_eq!(natural_cmp("Track 2", "Track 10"), Ordering::Less);
+    assert_eq!(natural_cmp("track 02", "Track 2"), Ordering::Equal);
+    assert_eq!(natural_cmp("abba", "ABC"), Ordering::Less);
+    assert_eq!(natural_cmp("Track", "Track 1"), Ordering::Less);
+}
+
+#[test]
+fn test_sort_preset_breaks_ties_with_later_keys() {
+    let preset = SortPreset::new("Newest first", &["-year", "title"]);
+    let tracks = [
+        track("Track 10", "B", Some(2015)),
+        track("Track 2", "B", Some(2015)),
+        track("Intro", "A", None),
+        track("Outro", "C", Some(2020)),
+    ];
+    let stats = Stats::default();
+
+    let mut order: Vec<usize> = (0..tracks.len()).collect();
+    order.sort_by(|a, b| preset.compare(&tracks[*a], &tracks[*b], &stats));
+    assert_eq!(order, vec![3, 1, 0, 2]);
+}
+
+#[test]
+fn test_sort_key_parsing() {
+    let key = SortKey::try_from(String::from("-play_count")).unwrap();
+    assert_eq!(key.field, SortField::PlayCount);
+    assert_eq!(key.direction, Direction::Descending);
+    assert!(SortKey::try_from(String::from("mood")).is_err());
+
+    let preset: SortPreset = toml::from_str("name = \"Album\"\nkeys = [\"album\", \"track\"]")
+        .expect("preset should parse");
+    assert_eq!(preset.keys.len(), 2);
+}
+
+#[test]
+fn test_presets_compare_by_keys() {
+    let custom = SortPreset::new("Artist", &["artist", "-year"]);
+    assert_ne!(Order::Sort(custom), Order::Sort(SortPreset::artist()));
+    assert_eq!(
+        Order::Sort(SortPreset::artist()),
+        Order::Sort(builtin_presets()[1].clone())
+    );
+}
@@ -3,12 +3,16 @@ use std::{
     fs::File,
     io::BufReader,
     path::{Path, PathBuf},
-    sync::mpsc,
+    sync::{atomic::Ordering, mpsc},
     thread,
-    time::{self, Duration},
+    time::{self, Duration, Instant},
 };
 
 use crate::Command;
+use crate::spectrum::{Analyzer, Samples, SharedSamples, Spectrum, Tap};
+
+// The visualizer is redrawn at most this often, the FFT is the expensive part on a Pi.
+const VISUALIZER_INTERVAL: Duration = Duration::from_millis(66);
 
 pub(crate) struct SinkState {
     pub _is_paused: bool,
@@ -17,6 +21,7 @@ pub(crate) struct SinkState {
     pub current_track_finished: bool,
     pub position: Duration,
     pub volume: f32,
+    pub spectrum: Spectrum,
 }
 
 pub fn setup() -> (mpsc::Sender<Command>, mpsc::Receiver<SinkState>) {
@@ -31,10 +36,25 @@ pub fn setup() -> (mpsc::Sender<Command>, mpsc::Receiver<SinkState>) {
                 let mut was_playing = false;
                 let mut sink_state;
                 let mut current_track_finished = false;
+                let samples = SharedSamples::default();
+                // Copied out of the lock so the taps never wait on the FFT.
+                let mut copied = Samples::default();
+                let mut analyzer = Analyzer::default();
+                let mut is_visualizing = false;
+                let mut spectrum = Spectrum::default();
+                let mut analyzed_at = Instant::now();
 
                 loop {
-                    if let Ok(command) = command_rx.try_recv() {
-                        audio_command(command, &sink);
+                    match command_rx.try_recv() {
+                        Ok(Command::Visualize(on)) => {
+                            is_visualizing = on;
+                            samples.is_on.store(on, Ordering::Relaxed);
+                            if let Ok(mut samples) = samples.samples.lock() {
+                                samples.recent.clear();
+                            }
+                        }
+                        Ok(command) => audio_command(command, &sink, &samples),
+                        Err(_) => {}
                     }
 
                     let is_playing = !sink.empty() && !sink.is_paused();
@@ -42,6 +62,18 @@ pub fn setup() -> (mpsc::Sender<Command>, mpsc::Receiver<SinkState>) {
                         current_track_finished = true;
                     }
 
+                    if !is_visualizing || !is_playing {
+                        spectrum = Spectrum::default();
+                    } else if analyzed_at.elapsed() >= VISUALIZER_INTERVAL {
+                        if let Ok(samples) = samples.samples.lock() {
+                            copied.rate = samples.rate;
+                            copied.recent.clear();
+                            copied.recent.extend(&samples.recent);
+                        }
+                        spectrum = analyzer.analyze(&copied);
+                        analyzed_at = Instant::now();
+                    }
+
                     sink_state = SinkState {
                         _is_paused: sink.is_paused(),
                         is_empty: sink.empty(),
@@ -49,6 +81,7 @@ pub fn setup() -> (mpsc::Sender<Command>, mpsc::Receiver<SinkState>) {
                         current_track_finished,
                         position: sink.get_pos(),
                         volume: sink.volume(),
+                        spectrum: spectrum.clone(),
                     };
 
                     current_track_finished = false;
@@ -66,16 +99,18 @@ pub fn setup() -> (mpsc::Sender<Command>, mpsc::Receiver<SinkState>) {
     (command_tx, state_rx)
 }
 
-fn audio_command(_message: Command, sink: &Sink) {
+fn audio_command(_message: Command, sink: &Sink, samples: &SharedSamples) {
     match _message {
         Command::PlayPause(path) => play_pause(sink, &path),
-        Command::New(path) => new_song(sink, &path),
+        Command::New(path) => new_song(sink, &path, samples),
         Command::Forward(distance, length) => seek_forward(sink, distance, length),
         Command::Backward(distance) => seek_backward(sink, distance),
         Command::_Next(_, _) => next(sink),
-        Command::_Append(path, _) => append(sink, &path),
+        Command::_Append(path, _) => append(sink, &path, samples),
         Command::_Previous(_, _) => todo!(),
         Command::Volume(step) => volume_control(sink, step),
+        // Taken care of by the playback loop.
+        Command::Visualize(_) => {}
     }
 }
 
@@ -92,21 +127,21 @@ fn next(sink: &Sink) {
     sink.skip_one();
 }
 
-fn append(sink: &Sink, path: &PathBuf) {
+fn append(sink: &Sink, path: &PathBuf, samples: &SharedSamples) {
     let file = File::open(path).unwrap();
     let buffer = BufReader::new(file);
     let source = Decoder::new(buffer).unwrap();
 
-    sink.append(source);
+    sink.append(Tap::new(source, samples.clone()));
 }
 
-fn new_song(sink: &Sink, path: &PathBuf) {
+fn new_song(sink: &Sink, path: &PathBuf, samples: &SharedSamples) {
     if sink.is_paused() {
         let file = File::open(path).unwrap();
         let buffer = BufReader::new(file);
         let source = Decoder::new(buffer).unwrap();
 
-        sink.append(source);
+        sink.append(Tap::new(source, samples.clone()));
         sink.skip_one();
         sink.play();
     } else {
@@ -115,7 +150,7 @@ fn new_song(sink: &Sink, path: &PathBuf) {
         let buffer = BufReader::new(file);
         let source = Decoder::new(buffer).unwrap();
 
-        sink.append(source);
+        sink.append(Tap::new(source, samples.clone()));
     }
 }
 
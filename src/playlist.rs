@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Audio;
+use crate::stats::{Stats, now};
+use smart::SmartPlaylist;
+pub(crate) mod formats;
+pub(crate) mod smart;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Playlist {
+    pub name: String,
+    // Paths rather than positions in `state.tracks`, so they survive rescans and removals.
+    #[serde(default)]
+    pub tracks: Vec<PathBuf>,
+    #[serde(skip)]
+    pub origin: Origin,
+}
+
+// Only daph's own playlists can be changed, the others are rebuilt on every start.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) enum Origin {
+    #[default]
+    Own,
+    // The .m3u, .pls or .xspf file it was read from.
+    File(PathBuf),
+    // Filled from the rules of a smart playlist in daph.toml.
+    Smart,
+}
+
+// The user's playlists, kept in playlists.toml next to daph.toml.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Playlists {
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+    // Where adding a track puts it, the last playlist that was created or opened.
+    #[serde(skip)]
+    pub target: Option<usize>,
+    #[serde(default, rename = "playlist")]
+    pub lists: Vec<Playlist>,
+    #[serde(skip)]
+    pub smart: Vec<SmartPlaylist>,
+}
+
+impl Playlists {
+    pub fn load(path: PathBuf) -> Self {
+        let mut playlists: Playlists = match fs::read_to_string(&path) {
+            Ok(file) => toml::from_str(&file).unwrap_or_else(|_| {
+                eprintln!("\nCan't read the playlists: {}", path.display());
+                Playlists::default()
+            }),
+            Err(_) => Playlists::default(),
+        };
+        playlists.path = Some(path);
+        if !playlists.lists.is_empty() {
+            playlists.target = Some(0);
+        }
+        playlists
+    }
+
+    // Adds the playlist files found by the library scan, after daph's own playlists.
+    // Entries that match a scanned track take its path, the others stay in as they are, so
+    // they show up as missing.
+    pub fn import(&mut self, files: &[PathBuf], tracks: &[Audio]) {
+        if files.is_empty() {
+            return;
+        }
+        let known: HashMap<PathBuf, &Path> = tracks
+            .iter()
+            .map(|track| (formats::comparable(&track.path), track.path.as_path()))
+            .collect();
+        for file in files {
+            let Ok(bytes) = fs::read(file) else {
+                eprintln!("\nCan't read the playlist: {}", file.display());
+                continue;
+            };
+            // Old .m3u files are usually Latin-1 rather than UTF-8.
+            let text = String::from_utf8(bytes)
+                .unwrap_or_else(|error| error.into_bytes().iter().map(|c| *c as char).collect());
+
+            let mut entries = Vec::new();
+            for location in formats::parse(file, &text) {
+                let resolved = formats::resolve(&location, file);
+                let track = resolved
+                    .as_ref()
+                    .and_then(|path| known.get(&formats::comparable(path)));
+                match track {
+                    Some(track) => entries.push(track.to_path_buf()),
+                    None => {
+                        eprintln!("\nUnresolved entry in {}: {location}", file.display());
+                        entries.extend(resolved);
+                    }
+                }
+            }
+
+            let name = file
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            self.lists.push(Playlist {
+                name,
+                tracks: entries,
+                origin: Origin::File(file.clone()),
+            });
+        }
+    }
+
+    // Smart playlists come last and are filled again from scratch, so they follow the
+    // library and the play counts, ratings and play times in the stats.
+    pub fn refresh_smart(&mut self, tracks: &[Audio], stats: &Stats) {
+        self.lists
+            .retain(|playlist| playlist.origin != Origin::Smart);
+        let now = now();
+        for smart in &self.smart {
+            self.lists.push(Playlist {
+                name: smart.name.clone(),
+                tracks: smart
+                    .evaluate(tracks, stats, now)
+                    .into_iter()
+                    .map(|index| tracks[index].path.clone())
+                    .collect(),
+                origin: Origin::Smart,
+            });
+        }
+    }
+
+    pub fn is_read_only(&self, list: usize) -> bool {
+        self.lists
+            .get(list)
+            .is_some_and(|playlist| playlist.origin != Origin::Own)
+    }
+
+    // Read-only playlists can't take new tracks, so they never become the target.
+    pub fn set_target(&mut self, list: usize) {
+        if list < self.lists.len() && !self.is_read_only(list) {
+            self.target = Some(list);
+        }
+    }
+
+    // Writes the playlist to `dir` as <name>.m3u8 and returns where it went.
+    pub fn export(&self, list: usize, tracks: &[Audio], dir: &Path) -> io::Result<PathBuf> {
+        let Some(playlist) = self.lists.get(list) else {
+            return Err(io::Error::from(io::ErrorKind::NotFound));
+        };
+        let name: String = playlist
+            .name
+            .chars()
+            .map(|c| match c {
+                '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+                c => c,
+            })
+            .collect();
+        let path = dir.join(format!("{name}.m3u8"));
+        fs::create_dir_all(dir)?;
+        fs::write(&path, formats::to_m3u8(&playlist.tracks, tracks))?;
+        Ok(path)
+    }
+
+    pub fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let own = Playlists {
+            lists: self
+                .lists
+                .iter()
+                .filter(|playlist| playlist.origin == Origin::Own)
+                .cloned()
+                .collect(),
+            ..Default::default()
+        };
+        match toml::to_string(&own) {
+            Ok(file) => fs::write(path, file).unwrap_or(()),
+            Err(_) => log::warn!("Can't write the playlists: {}", path.display()),
+        }
+    }
+
+    // Names stay unique, a taken one gets a number after it. Own playlists come first, so
+    // it goes in before the imported and smart ones. Returns where it ended up.
+    pub fn create(&mut self, name: &str) -> usize {
+        let name = self.unique_name(name, None);
+        let list = self
+            .lists
+            .iter()
+            .position(|playlist| playlist.origin != Origin::Own)
+            .unwrap_or(self.lists.len());
+        self.lists.insert(
+            list,
+            Playlist {
+                name,
+                tracks: Vec::new(),
+                origin: Origin::Own,
+            },
+        );
+        self.target = Some(list);
+        self.save();
+        list
+    }
+
+    pub fn rename(&mut self, list: usize, name: &str) {
+        if list >= self.lists.len() || self.is_read_only(list) {
+            return;
+        }
+        self.lists[list].name = self.unique_name(name, Some(list));
+        self.save();
+    }
+
+    pub fn delete(&mut self, list: usize) {
+        if list >= self.lists.len() || self.is_read_only(list) {
+            return;
+        }
+        self.lists.remove(list);
+        self.target = match self.target {
+            Some(target) if target == list => None,
+            Some(target) if target > list => Some(target - 1),
+            target => target,
+        };
+        self.save();
+    }
+
+    // Adds to the target playlist, making one first when there are none.
+    pub fn add(&mut self, path: &Path) {
+        let target = self.target.filter(|target| !self.is_read_only(*target));
+        let list = match target.filter(|target| *target < self.lists.len()) {
+            Some(target) => target,
+            None => self.create("New playlist"),
+        };
+        self.lists[list].tracks.push(path.to_path_buf());
+        self.save();
+    }
+
+    pub fn remove_entry(&mut self, list: usize, position: usize) {
+        if self.is_read_only(list) {
+            return;
+        }
+        if let Some(playlist) = self.lists.get_mut(list)
+            && position < playlist.tracks.len()
+        {
+            playlist.tracks.remove(position);
+            self.save();
+        }
+    }
+
+    // Where each entry of a playlist is in `tracks`, None for files that are gone.
+    pub fn resolve(&self, list: usize, tracks: &[Audio]) -> Vec<Option<usize>> {
+        let Some(playlist) = self.lists.get(list) else {
+            return Vec::new();
+        };
+        let positions: HashMap<&Path, usize> = tracks
+            .iter()
+            .enumerate()
+            .map(|(index, track)| (track.path.as_path(), index))
+            .collect();
+        playlist
+            .tracks
+            .iter()
+            .map(|path| positions.get(path.as_path()).copied())
+            .collect()
+    }
+
+    fn unique_name(&self, name: &str, skip: Option<usize>) -> String {
+        let name = match name.trim() {
+            "" => "New playlist",
+            name => name,
+        };
+        let is_taken = |candidate: &str| {
+            self.lists
+                .iter()
+                .enumerate()
+                .any(|(list, playlist)| Some(list) != skip && playlist.name == candidate)
+        };
+        let mut candidate = name.to_string();
+        let mut number = 2;
+        while is_taken(&candidate) {
+            candidate = format!("{name} {number}");
+            number += 1;
+        }
+        candidate
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,144 @@
+use std::fs;
+use std::path::{self, Component, Path, PathBuf};
+
+use crate::Audio;
+
+const EXTENSIONS: [&str; 4] = ["m3u", "m3u8", "pls", "xspf"];
+
+pub(crate) fn is_playlist_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+// The locations listed in a playlist file, as written.
+pub(crate) fn parse(path: &Path, text: &str) -> Vec<String> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+    match extension.as_deref() {
+        Some("pls") => parse_pls(text),
+        Some("xspf") => parse_xspf(text),
+        _ => parse_m3u(text),
+    }
+}
+
+// Everything that isn't a comment or an #EXT directive is a location.
+fn parse_m3u(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| line.trim().trim_start_matches('\u{feff}'))
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+// File1=..., File2=... under [playlist]. Title and Length lines are skipped.
+fn parse_pls(text: &str) -> Vec<String> {
+    text.lines()
+        .filter_map(|line| line.trim().split_once('='))
+        .filter(|(key, _)| key.to_lowercase().starts_with("file"))
+        .map(|(_, location)| location.trim().to_string())
+        .filter(|location| !location.is_empty())
+        .collect()
+}
+
+// Only the <location> of every track is needed, so there's no XML parser behind this.
+fn parse_xspf(text: &str) -> Vec<String> {
+    let mut locations = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("<location>") {
+        rest = &rest[start + "<location>".len()..];
+        let Some(end) = rest.find("</location>") else {
+            break;
+        };
+        locations.push(unescape_xml(rest[..end].trim()));
+        rest = &rest[end..];
+    }
+    locations
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+// Relative locations start from the playlist's own directory. `file://` URIs are decoded,
+// other URLs can't point into the library and give None.
+pub(crate) fn resolve(location: &str, playlist: &Path) -> Option<PathBuf> {
+    let location = match location.strip_prefix("file://") {
+        Some(uri) => decode_percent(uri.strip_prefix("localhost").unwrap_or(uri)),
+        None if location.contains("://") => return None,
+        None => location.replace('\\', "/"),
+    };
+    let path = Path::new(&location);
+    let path = match path.is_absolute() {
+        true => path.to_path_buf(),
+        false => playlist.parent().unwrap_or(Path::new("")).join(path),
+    };
+    Some(normalize(&path))
+}
+
+// What playlist entries and library tracks are matched by, so a file gives the same key
+// whether it's reached through a relative library root, `..` or a symlink. Files that are
+// gone can't be canonicalized and are only made absolute and tidied up.
+pub(crate) fn comparable(path: &Path) -> PathBuf {
+    let absolute = || path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    fs::canonicalize(path).unwrap_or_else(|_| normalize(&absolute()))
+}
+
+// Drops `.` and folds `..` without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normal.pop();
+            }
+            component => normal.push(component),
+        }
+    }
+    normal
+}
+
+fn decode_percent(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// One location per line, with the #EXTINF line players use for the title.
+pub(crate) fn to_m3u8(paths: &[PathBuf], tracks: &[Audio]) -> String {
+    let mut text = String::from("#EXTM3U\n");
+    for path in paths {
+        if let Some(track) = tracks.iter().find(|track| track.path == *path) {
+            text.push_str(&format!(
+                "#EXTINF:{},{} - {}\n",
+                track.length, track.author, track.name
+            ));
+        }
+        text.push_str(&format!("{}\n", path.display()));
+    }
+    text
+}
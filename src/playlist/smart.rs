@@ -0,0 +1,207 @@
+use std::cmp::Ordering;
+
+use serde::Deserialize;
+
+use crate::Audio;
+use crate::order::{SortField, SortKey, compare_keys, natural_cmp};
+use crate::stats::Stats;
+
+// A playlist filled from rules instead of by hand, set up in daph.toml:
+//
+// [[smart_playlist]]
+// name = "Forgotten favourites"
+// rules = ["rating >= 4", "last_played > 30d"]
+// sort = ["-rating", "title"]
+// limit = 50
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct SmartPlaylist {
+    pub name: String,
+    // A track has to pass all of them.
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    #[serde(default)]
+    pub sort: Vec<SortKey>,
+    pub limit: Option<usize>,
+}
+
+impl SmartPlaylist {
+    // Positions in `tracks`, sorted and cut to the limit.
+    pub fn evaluate(&self, tracks: &[Audio], stats: &Stats, now: u64) -> Vec<usize> {
+        let mut matched: Vec<usize> = (0..tracks.len())
+            .filter(|index| {
+                self.rules
+                    .iter()
+                    .all(|rule| rule.matches(&tracks[*index], stats, now))
+            })
+            .collect();
+        matched.sort_by(|a, b| compare_keys(&self.sort, &tracks[*a], &tracks[*b], stats));
+        if let Some(limit) = self.limit {
+            matched.truncate(limit);
+        }
+        matched
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Text(String),
+    Number(f64),
+}
+
+// "<field> <operator> <value>", e.g. "genre = jazz" or "year < 1970". The fields are the sort
+// key names. `added` and `last_played` compare by how long ago, so "added < 2w" means the last
+// two weeks and "last_played > 30d" also takes tracks that were never played.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub(crate) struct Rule {
+    field: SortField,
+    operator: Operator,
+    value: Value,
+}
+
+impl TryFrom<String> for Rule {
+    type Error = String;
+
+    fn try_from(rule: String) -> Result<Self, Self::Error> {
+        let split = rule
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rule.len());
+        let (name, rest) = rule.split_at(split);
+        let Some(field) = SortField::from_name(name) else {
+            return Err(format!("Unknown field in rule: {rule}"));
+        };
+
+        let rest = rest.trim_start();
+        let operators = [
+            (">=", Operator::GreaterOrEqual),
+            ("<=", Operator::LessOrEqual),
+            ("!=", Operator::NotEqual),
+            ("=", Operator::Equal),
+            ("<", Operator::Less),
+            (">", Operator::Greater),
+            ("~", Operator::Contains),
+        ];
+        let Some((operator, value)) = operators
+            .iter()
+            .find_map(|(symbol, operator)| Some((*operator, rest.strip_prefix(symbol)?)))
+        else {
+            return Err(format!("Missing operator in rule: {rule}"));
+        };
+        let value = value.trim().trim_matches('"');
+
+        let value = match is_text(field) {
+            true => Value::Text(value.to_lowercase()),
+            false if operator == Operator::Contains => {
+                return Err(format!("Only text fields take '~': {rule}"));
+            }
+            false => match parse_number(value) {
+                Some(number) => Value::Number(number),
+                None => return Err(format!("Not a number in rule: {rule}")),
+            },
+        };
+        Ok(Rule {
+            field,
+            operator,
+            value,
+        })
+    }
+}
+
+impl Rule {
+    pub fn matches(&self, track: &Audio, stats: &Stats, now: u64) -> bool {
+        let ordering = match (&self.value, field_value(self.field, track, stats, now)) {
+            (Value::Text(wanted), Some(Value::Text(text))) => {
+                let text = text.to_lowercase();
+                match self.operator {
+                    Operator::Contains => return text.contains(wanted.as_str()),
+                    Operator::Equal | Operator::NotEqual => text.cmp(wanted),
+                    _ => natural_cmp(&text, wanted),
+                }
+            }
+            (Value::Number(wanted), Some(Value::Number(number))) => {
+                number.partial_cmp(wanted).unwrap_or(Ordering::Equal)
+            }
+            // Tracks without the tag only pass "not equal".
+            _ => return self.operator == Operator::NotEqual,
+        };
+        match self.operator {
+            Operator::Equal => ordering == Ordering::Equal,
+            Operator::NotEqual => ordering != Ordering::Equal,
+            Operator::Less => ordering == Ordering::Less,
+            Operator::LessOrEqual => ordering != Ordering::Greater,
+            Operator::Greater => ordering == Ordering::Greater,
+            Operator::GreaterOrEqual => ordering != Ordering::Less,
+            Operator::Contains => false,
+        }
+    }
+}
+
+fn is_text(field: SortField) -> bool {
+    matches!(
+        field,
+        SortField::Title
+            | SortField::Artist
+            | SortField::Album
+            | SortField::Genre
+            | SortField::Path
+    )
+}
+
+fn field_value(field: SortField, track: &Audio, stats: &Stats, now: u64) -> Option<Value> {
+    let age = |time: u64| Value::Number(now.saturating_sub(time) as f64);
+    let value = match field {
+        SortField::Title => Value::Text(track.name.clone()),
+        SortField::Artist => Value::Text(track.author.clone()),
+        SortField::Album => Value::Text(track.album.clone()),
+        SortField::Genre => Value::Text(track.genre.clone()),
+        SortField::Path => Value::Text(track.path.display().to_string()),
+        SortField::Year => Value::Number(track.year? as f64),
+        SortField::Track => Value::Number(track.track_number? as f64),
+        SortField::Length => Value::Number(track.length as f64),
+        SortField::Added => age(track.added),
+        SortField::PlayCount => Value::Number(stats.get(&track.path).play_count as f64),
+        SortField::SkipCount => Value::Number(stats.get(&track.path).skip_count as f64),
+        SortField::LastPlayed => match stats.get(&track.path).last_played {
+            Some(time) => age(time),
+            None => Value::Number(f64::INFINITY),
+        },
+        SortField::Rating => Value::Number(stats.get(&track.path).rating? as f64),
+        SortField::Favourite => Value::Number(stats.get(&track.path).favourite as u8 as f64),
+    };
+    Some(value)
+}
+
+// Plain numbers, true/false, or spans of time like "90s", "5m", "12h", "30d", "2w" and "1y"
+// which come out in seconds.
+fn parse_number(value: &str) -> Option<f64> {
+    match value {
+        "true" | "yes" => return Some(1.0),
+        "false" | "no" => return Some(0.0),
+        _ => {}
+    }
+    if let Ok(number) = value.parse() {
+        return Some(number);
+    }
+    let unit = match value.chars().last()? {
+        's' => 1.0,
+        'm' => 60.0,
+        'h' => 60.0 * 60.0,
+        'd' => 24.0 * 60.0 * 60.0,
+        'w' => 7.0 * 24.0 * 60.0 * 60.0,
+        'y' => 365.0 * 24.0 * 60.0 * 60.0,
+        _ => return None,
+    };
+    let number: f64 = value[..value.len() - 1].trim().parse().ok()?;
+    Some(number * unit)
+}
@@ -0,0 +1,147 @@
+use super::*;
+
+#[test]
+fn test_names_stay_unique() {
+    let mut playlists = Playlists::default();
+    playlists.create("Road trip");
+    playlists.create("Road trip");
+    playlists.create("  ");
+    let names: Vec<&str> = playlists
+        .lists
+        .iter()
+        .map(|list| list.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["Road trip", "Road trip 2", "New playlist"]);
+
+    playlists.rename(1, "Road trip");
+    assert_eq!(playlists.lists[1].name, "Road trip 2");
+    playlists.rename(0, "Road trip");
+    assert_eq!(playlists.lists[0].name, "Road trip");
+}
+
+#[test]
+fn test_add_and_resolve() {
+    let track = |path: &str| Audio {
+        path: PathBuf::from(path),
+        ..Default::default()
+    };
+    let tracks = vec![track("/music/a.flac"), track("/music/b.flac")];
+
+    let mut playlists = Playlists::default();
+    // Adding with no playlists around makes one.
+    playlists.add(Path::new("/music/b.flac"));
+    playlists.add(Path::new("/music/gone.flac"));
+    playlists.add(Path::new("/music/a.flac"));
+    assert_eq!(playlists.lists.len(), 1);
+    assert_eq!(playlists.resolve(0, &tracks), vec![Some(1), None, Some(0)]);
+
+    playlists.create("Second");
+    playlists.delete(0);
+    assert_eq!(playlists.target, Some(0));
+    assert_eq!(playlists.lists[0].name, "Second");
+}
+
+#[test]
+fn test_parse_formats() {
+    let m3u = "#EXTM3U\n#EXTINF:287,toe - Goodbye\ntoe/Goodbye.flac\n\n/music/Adele/Hello.mp3\n";
+    assert_eq!(
+        formats::parse(Path::new("a.m3u8"), m3u),
+        vec!["toe/Goodbye.flac", "/music/Adele/Hello.mp3"]
+    );
+
+    let pls =
+        "[playlist]\nFile1=toe/Goodbye.flac\nTitle1=Goodbye\nfile2=Hello.mp3\nNumberOfEntries=2\n";
+    assert_eq!(
+        formats::parse(Path::new("a.PLS"), pls),
+        vec!["toe/Goodbye.flac", "Hello.mp3"]
+    );
+
+    let xspf = "<playlist><trackList>\
+        <track><location>file:///music/toe/Two%20Moons.flac</location></track>\
+        <track><title>x</title><location>Tom &amp; Jerry.mp3</location></track>\
+        </trackList></playlist>";
+    assert_eq!(
+        formats::parse(Path::new("a.xspf"), xspf),
+        vec!["file:///music/toe/Two%20Moons.flac", "Tom & Jerry.mp3"]
+    );
+}
+
+#[test]
+fn test_resolve_locations() {
+    let playlist = Path::new("/music/lists/road.m3u");
+    let resolve = |location: &str| formats::resolve(location, playlist);
+    assert_eq!(
+        resolve("../toe/Goodbye.flac"),
+        Some(PathBuf::from("/music/toe/Goodbye.flac"))
+    );
+    assert_eq!(
+        resolve("..\\toe\\Goodbye.flac"),
+        Some(PathBuf::from("/music/toe/Goodbye.flac"))
+    );
+    assert_eq!(
+        resolve("file:///music/toe/Two%20Moons.flac"),
+        Some(PathBuf::from("/music/toe/Two Moons.flac"))
+    );
+    assert_eq!(resolve("http://radio.example/stream"), None);
+}
+
+#[test]
+fn test_smart_playlist() {
+    const DAY: u64 = 24 * 60 * 60;
+    let now = 1_000 * DAY;
+    let track = |name: &str, genre: &str, year: u32, added_days_ago: u64| Audio {
+        name: String::from(name),
+        genre: String::from(genre),
+        year: Some(year),
+        added: now - added_days_ago * DAY,
+        path: PathBuf::from(format!("/music/{name}.flac")),
+        ..Default::default()
+    };
+    let tracks = vec![
+        track("So What", "Jazz", 1959, 400),
+        track("Take Five", "jazz", 1959, 3),
+        track("Giant Steps", "Jazz", 1960, 10),
+        track("Hello", "Pop", 2015, 1),
+    ];
+    let mut stats = Stats::default();
+    stats.tracks.insert(
+        tracks[0].path.clone(),
+        crate::stats::TrackStats {
+            rating: Some(5),
+            last_played: Some(now - 60 * DAY),
+            ..Default::default()
+        },
+    );
+    stats.tracks.insert(
+        tracks[1].path.clone(),
+        crate::stats::TrackStats {
+            rating: Some(4),
+            last_played: Some(now - DAY),
+            ..Default::default()
+        },
+    );
+
+    let smart = |rules: &str| -> SmartPlaylist {
+        toml::from_str(&format!("name = \"Smart\"\nsort = [\"title\"]\n{rules}")).unwrap()
+    };
+    let old_jazz = smart("rules = [\"genre = jazz\", \"year < 1970\"]");
+    assert_eq!(old_jazz.evaluate(&tracks, &stats, now), vec![2, 0, 1]);
+
+    let forgotten = smart("rules = [\"rating >= 4\", \"last_played > 30d\"]");
+    assert_eq!(forgotten.evaluate(&tracks, &stats, now), vec![0]);
+
+    let fresh = smart("rules = [\"added < 2w\"]\nlimit = 2");
+    assert_eq!(fresh.evaluate(&tracks, &stats, now), vec![2, 3]);
+
+    assert!(toml::from_str::<SmartPlaylist>("name = \"x\"\nrules = [\"mood = happy\"]").is_err());
+    assert!(toml::from_str::<SmartPlaylist>("name = \"x\"\nrules = [\"year ~ 19\"]").is_err());
+}
+
+#[test]
+fn test_comparable_paths() {
+    assert_eq!(
+        formats::comparable(Path::new("./music/../music/toe/Goodbye.flac")),
+        formats::comparable(Path::new("music/toe/Goodbye.flac"))
+    );
+    assert!(formats::comparable(Path::new("music/toe/Goodbye.flac")).is_absolute());
+}
@@ -0,0 +1,190 @@
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Repeat {
+    Off,
+    All,
+    One,
+    StopAfterCurrent,
+}
+
+impl Display for Repeat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Repeat::Off => write!(f, "Repeat off"),
+            Repeat::All => write!(f, "Repeat all"),
+            Repeat::One => write!(f, "Repeat one"),
+            Repeat::StopAfterCurrent => write!(f, "Stop after current"),
+        }
+    }
+}
+
+impl Repeat {
+    // Whether stepping past either end of the queue comes back around.
+    pub fn wraps(&self) -> bool {
+        matches!(self, Repeat::All | Repeat::One)
+    }
+}
+
+// What plays next. Holds positions in `state.tracks`, so reordering the library table never
+// touches it and the same track may be queued more than once.
+#[derive(Debug, Default)]
+pub(crate) struct Queue {
+    pub tracks: Vec<usize>,
+    pub cursor: Option<usize>,
+    // Played straight from the library table and not edited since, so it may follow the
+    // table when that's sorted again.
+    pub is_from_library: bool,
+}
+
+impl Queue {
+    pub fn replace(&mut self, tracks: Vec<usize>, start: usize) {
+        self.cursor = match start < tracks.len() {
+            true => Some(start),
+            false => None,
+        };
+        self.tracks = tracks;
+        self.is_from_library = false;
+    }
+
+    pub fn current(&self) -> Option<usize> {
+        self.cursor.and_then(|cursor| self.tracks.get(cursor).copied())
+    }
+
+    // Returns None and stays put at the end of the queue unless it wraps.
+    pub fn next(&mut self, wrap: bool) -> Option<usize> {
+        let cursor = self.cursor?;
+        if cursor + 1 == self.tracks.len() && !wrap {
+            return None;
+        }
+        self.cursor = Some((cursor + 1) % self.tracks.len());
+        self.current()
+    }
+
+    pub fn previous(&mut self, wrap: bool) -> Option<usize> {
+        let cursor = self.cursor?;
+        if cursor == 0 && !wrap {
+            return None;
+        }
+        let size = self.tracks.len();
+        self.cursor = Some((cursor + size - 1) % size);
+        self.current()
+    }
+
+    // An idle queue has nothing for the new entry to follow, so it becomes the current one.
+    pub fn append(&mut self, index: usize) {
+        self.is_from_library = false;
+        self.tracks.push(index);
+        if self.cursor.is_none() {
+            self.cursor = Some(self.tracks.len() - 1);
+        }
+    }
+
+    pub fn play_next(&mut self, index: usize) {
+        self.is_from_library = false;
+        match self.cursor {
+            Some(cursor) => self.tracks.insert(cursor + 1, index),
+            None => {
+                self.tracks.insert(0, index);
+                self.cursor = Some(0);
+            }
+        }
+    }
+
+    // Where the upcoming part of the queue starts, the queue screen only lists from here.
+    pub fn upcoming_start(&self) -> usize {
+        self.cursor.unwrap_or(0)
+    }
+
+    // Everything after the current entry, all of it when nothing plays from the queue.
+    pub fn upcoming(&self) -> &[usize] {
+        match self.cursor {
+            Some(cursor) => &self.tracks[(cursor + 1).min(self.tracks.len())..],
+            None => &self.tracks,
+        }
+    }
+
+    // Removes one entry, the current one has to stay while it plays.
+    pub fn remove(&mut self, position: usize) {
+        if position >= self.tracks.len() || Some(position) == self.cursor {
+            return;
+        }
+        self.is_from_library = false;
+        self.tracks.remove(position);
+        if let Some(cursor) = self.cursor
+            && position < cursor
+        {
+            self.cursor = Some(cursor - 1);
+        }
+    }
+
+    // Swaps an upcoming entry with its neighbour, returns where it ended up.
+    pub fn move_entry(&mut self, position: usize, down: bool) -> usize {
+        let target = match down {
+            true => position + 1,
+            false => position.saturating_sub(1),
+        };
+        let start = match self.cursor {
+            Some(cursor) => cursor + 1,
+            None => 0,
+        };
+        if position < start || target < start || target >= self.tracks.len() {
+            return position;
+        }
+        self.is_from_library = false;
+        self.tracks.swap(position, target);
+        target
+    }
+
+    pub fn jump(&mut self, position: usize) -> Option<usize> {
+        if position >= self.tracks.len() {
+            return None;
+        }
+        self.cursor = Some(position);
+        self.current()
+    }
+
+    // The current track stays put and becomes the first one, `arrange` reorders everything else.
+    pub fn shuffle<F>(&mut self, arrange: F)
+    where
+        F: FnOnce(&mut Vec<usize>),
+    {
+        match self.cursor {
+            Some(cursor) => {
+                let current = self.tracks.remove(cursor);
+                arrange(&mut self.tracks);
+                self.tracks.insert(0, current);
+                self.cursor = Some(0);
+            }
+            None => arrange(&mut self.tracks),
+        }
+    }
+
+    // Mirrors `Vec::remove` on the track list, the removed track leaves the queue entirely.
+    pub fn remove_track(&mut self, index: usize) {
+        let mut position = 0;
+        let cursor = self.cursor;
+        self.tracks.retain(|track| {
+            let keep = *track != index;
+            if !keep && cursor.is_some_and(|cursor| position < cursor) {
+                self.cursor = self.cursor.map(|cursor| cursor - 1);
+            }
+            position += 1;
+            keep
+        });
+        for track in self.tracks.iter_mut() {
+            if *track > index {
+                *track -= 1;
+            }
+        }
+
+        if self.tracks.is_empty() {
+            self.cursor = None;
+        } else if let Some(cursor) = self.cursor {
+            self.cursor = Some(cursor.min(self.tracks.len() - 1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
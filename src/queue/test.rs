@@ -0,0 +1,109 @@
+use super::*;
+use crate::utility::order_shuffle;
+
+#[test]
+fn test_wrap_around() {
+    let mut queue = Queue::default();
+    assert_eq!(queue.next(true), None);
+
+    queue.replace(vec![4, 2, 7], 2);
+    assert_eq!(queue.current(), Some(7));
+    assert_eq!(queue.next(true), Some(4));
+    assert_eq!(queue.previous(true), Some(7));
+}
+
+#[test]
+fn test_stop_at_the_ends() {
+    let mut queue = Queue::default();
+    queue.replace(vec![4, 2, 7], 2);
+    assert_eq!(queue.next(false), None);
+    assert_eq!(queue.current(), Some(7));
+
+    queue.replace(vec![4, 2, 7], 0);
+    assert_eq!(queue.previous(false), None);
+    assert_eq!(queue.next(false), Some(2));
+}
+
+#[test]
+fn test_shuffle_keeps_current() {
+    let mut queue = Queue::default();
+    queue.replace((0..50).collect(), 30);
+    queue.shuffle(|rest| order_shuffle(rest));
+
+    assert_eq!(queue.current(), Some(30));
+    assert_eq!(queue.cursor, Some(0));
+    let mut sorted = queue.tracks.clone();
+    sorted.sort();
+    assert_eq!(sorted, (0..50).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_remove_track() {
+    let mut queue = Queue::default();
+    queue.replace(vec![3, 1, 5, 1, 0], 2);
+
+    queue.remove_track(1);
+    assert_eq!(queue.tracks, vec![2, 4, 0]);
+    assert_eq!(queue.current(), Some(4));
+}
+
+#[test]
+fn test_edit_upcoming() {
+    let mut queue = Queue::default();
+    queue.replace(vec![0, 1, 2, 3], 1);
+
+    queue.play_next(9);
+    queue.append(8);
+    assert_eq!(queue.tracks, vec![0, 1, 9, 2, 3, 8]);
+
+    // The playing entry and everything before it can't be moved.
+    assert_eq!(queue.move_entry(1, true), 1);
+    assert_eq!(queue.move_entry(2, false), 2);
+    assert_eq!(queue.move_entry(2, true), 3);
+    assert_eq!(queue.tracks, vec![0, 1, 2, 9, 3, 8]);
+
+    queue.remove(1);
+    queue.remove(0);
+    assert_eq!(queue.tracks, vec![1, 2, 9, 3, 8]);
+    assert_eq!(queue.current(), Some(1));
+    assert_eq!(queue.jump(3), Some(3));
+}
+
+#[test]
+fn test_upcoming() {
+    let mut queue = Queue::default();
+    assert!(queue.upcoming().is_empty());
+    queue.replace(vec![4, 5, 6], 0);
+    assert_eq!(queue.upcoming(), &[5, 6]);
+    queue.jump(2);
+    assert!(queue.upcoming().is_empty());
+}
+
+#[test]
+fn test_edits_leave_the_library() {
+    let mut queue = Queue::default();
+    queue.replace(vec![0, 1, 2], 0);
+    queue.is_from_library = true;
+    queue.move_entry(1, true);
+    assert!(!queue.is_from_library);
+
+    queue.is_from_library = true;
+    queue.append(5);
+    assert!(!queue.is_from_library);
+}
+
+#[test]
+fn test_queue_from_fresh_start() {
+    let mut queue = Queue::default();
+    queue.append(5);
+    assert_eq!(queue.current(), Some(5));
+    queue.append(6);
+    assert_eq!(queue.next(false), Some(6));
+
+    let mut queue = Queue::default();
+    queue.play_next(3);
+    queue.play_next(4);
+    assert_eq!(queue.tracks, vec![3, 4]);
+    assert_eq!(queue.current(), Some(3));
+    assert_eq!(queue.next(false), Some(4));
+}
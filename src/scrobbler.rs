@@ -0,0 +1,76 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::Audio;
+use crate::history::Listen;
+
+const HEADER: &str = concat!(
+    "#AUDIOSCROBBLER/1.1\n#TZ/UTC\n#CLIENT/daph ",
+    env!("CARGO_PKG_VERSION"),
+    "\n"
+);
+
+// Last.fm doesn't take anything shorter.
+const MINIMUM_LENGTH: u64 = 30;
+
+// Completed plays in the Rockbox portable player format, so the usual desktop uploaders can
+// send them to Last.fm or ListenBrainz once the device is docked.
+#[derive(Debug, Default)]
+pub(crate) struct ScrobbleLog {
+    pub path: Option<PathBuf>,
+}
+
+impl ScrobbleLog {
+    pub fn new(path: PathBuf) -> Self {
+        ScrobbleLog { path: Some(path) }
+    }
+
+    pub fn record(&self, track: &Audio, listen: &Listen) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if !listen.is_play || track.length < MINIMUM_LENGTH {
+            return;
+        }
+
+        let is_new = fs::metadata(path).map_or(true, |metadata| metadata.len() == 0);
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| {
+                if is_new {
+                    file.write_all(HEADER.as_bytes())?;
+                }
+                writeln!(file, "{}", entry(track, listen))
+            });
+        if written.is_err() {
+            log::warn!("Can't write the scrobble log: {}", path.display());
+        }
+    }
+}
+
+// artist, album, title, track number, length, rating, timestamp and the MusicBrainz track id,
+// tab separated. The id is left empty for untagged files. "L" means listened, the format has
+// "S" for skipped but those aren't written.
+fn entry(track: &Audio, listen: &Listen) -> String {
+    let field = |text: &str| text.replace(['\t', '\n'], " ");
+    let number = track
+        .track_number
+        .map(|number| number.to_string())
+        .unwrap_or_default();
+    format!(
+        "{}\t{}\t{}\t{}\t{}\tL\t{}\t{}",
+        field(&track.author),
+        field(&track.album),
+        field(&track.name),
+        number,
+        track.length,
+        listen.time,
+        field(track.musicbrainz_id.as_deref().unwrap_or_default())
+    )
+}
+
+#[cfg(test)]
+mod test;
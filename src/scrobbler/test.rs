@@ -0,0 +1,29 @@
+use super::*;
+
+#[test]
+fn test_entry() {
+    let track = Audio {
+        name: String::from("Goodbye"),
+        author: String::from("toe"),
+        album: String::from("For Long\tTomorrow"),
+        track_number: Some(3),
+        length: 287,
+        ..Default::default()
+    };
+    let listen = Listen {
+        time: 1_700_000_000,
+        path: PathBuf::from("/music/toe/Goodbye.flac"),
+        listened: 287,
+        is_play: true,
+    };
+    assert_eq!(
+        entry(&track, &listen),
+        "toe\tFor Long Tomorrow\tGoodbye\t3\t287\tL\t1700000000\t"
+    );
+
+    let track = Audio {
+        musicbrainz_id: Some(String::from("5d3f6e2a-0c1b-4a8e-9b7d-2f1e6c4a9b30")),
+        ..track
+    };
+    assert!(entry(&track, &listen).ends_with("\t1700000000\t5d3f6e2a-0c1b-4a8e-9b7d-2f1e6c4a9b30"));
+}
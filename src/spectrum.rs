@@ -0,0 +1,260 @@
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use realfft::num_complex::Complex;
+use realfft::{RealFftPlanner, RealToComplex};
+use rodio::source::SeekError;
+use rodio::{Sample, Source};
+
+// About 46ms of audio at 44.1kHz, enough to tell the lowest bands apart.
+pub(crate) const FFT_SIZE: usize = 2048;
+pub(crate) const BANDS: usize = 32;
+// How many points the oscilloscope gets, one every few samples.
+const SCOPE_POINTS: usize = 256;
+// The bands are spread evenly on a log scale between these.
+const LOWEST: f32 = 40.0;
+const HIGHEST: f32 = 16_000.0;
+// Anything quieter shows as an empty band.
+const FLOOR_DB: f32 = -70.0;
+// How much of its height a bar keeps every frame when the sound under it stops.
+const FALLOFF: f32 = 0.8;
+// Samples are handed over in chunks, the audio thread shouldn't wait on the lock per sample.
+const CHUNK: usize = 256;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) enum Visualizer {
+    #[default]
+    Off,
+    Bars,
+    Scope,
+}
+
+impl Visualizer {
+    pub fn next(self) -> Self {
+        match self {
+            Visualizer::Off => Visualizer::Bars,
+            Visualizer::Bars => Visualizer::Scope,
+            Visualizer::Scope => Visualizer::Off,
+        }
+    }
+}
+
+// The latest mono samples of whatever the sink is playing.
+#[derive(Debug, Default)]
+pub(crate) struct Samples {
+    pub rate: u32,
+    pub recent: VecDeque<f32>,
+}
+
+// Between the playback thread and the taps. Nothing is copied while the visualizer is off.
+#[derive(Debug, Default)]
+pub(crate) struct Shared {
+    pub is_on: AtomicBool,
+    pub samples: Mutex<Samples>,
+}
+
+pub(crate) type SharedSamples = Arc<Shared>;
+
+// Sits between the decoder and the sink and copies what goes through it.
+pub(crate) struct Tap<S> {
+    inner: S,
+    shared: SharedSamples,
+    chunk: Vec<f32>,
+    // The channels of the frame being mixed down so far.
+    frame: f32,
+    channel: u16,
+}
+
+impl<S: Source> Tap<S>
+where
+    S::Item: Sample,
+{
+    pub fn new(inner: S, shared: SharedSamples) -> Self {
+        Tap {
+            inner,
+            shared,
+            chunk: Vec::with_capacity(CHUNK),
+            frame: 0.0,
+            channel: 0,
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Ok(mut samples) = self.shared.samples.lock() {
+            samples.rate = self.inner.sample_rate();
+            samples.recent.extend(self.chunk.drain(..));
+            let excess = samples.recent.len().saturating_sub(FFT_SIZE);
+            samples.recent.drain(..excess);
+        }
+        self.chunk.clear();
+    }
+}
+
+impl<S: Source> Iterator for Tap<S>
+where
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.inner.next()?;
+        if !self.shared.is_on.load(Ordering::Relaxed) {
+            return Some(sample);
+        }
+        let channels = self.inner.channels().max(1);
+        self.frame += sample.to_f32();
+        self.channel += 1;
+        if self.channel >= channels {
+            self.chunk.push(self.frame / channels as f32);
+            self.frame = 0.0;
+            self.channel = 0;
+            if self.chunk.len() >= CHUNK {
+                self.flush();
+            }
+        }
+        Some(sample)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<S: Source> Source for Tap<S>
+where
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.chunk.clear();
+        self.frame = 0.0;
+        self.channel = 0;
+        self.inner.try_seek(pos)
+    }
+}
+
+// What the visualizer draws, sent along with the sink state. Both are empty while it's off.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Spectrum {
+    // From the lowest band up, between 0 and 1.
+    pub bands: Vec<f32>,
+    // The waveform, between -1 and 1.
+    pub scope: Vec<f32>,
+}
+
+pub(crate) struct Analyzer {
+    fft: Arc<dyn RealToComplex<f32>>,
+    // Hann, so a loud band doesn't leak into its neighbours.
+    window: Vec<f32>,
+    input: Vec<f32>,
+    output: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    previous: Vec<f32>,
+}
+
+impl Default for Analyzer {
+    fn default() -> Self {
+        let fft = RealFftPlanner::<f32>::new().plan_fft_forward(FFT_SIZE);
+        let window = (0..FFT_SIZE)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / FFT_SIZE as f32).cos())
+            .collect();
+        Analyzer {
+            input: fft.make_input_vec(),
+            output: fft.make_output_vec(),
+            scratch: fft.make_scratch_vec(),
+            fft,
+            window,
+            previous: vec![0.0; BANDS],
+        }
+    }
+}
+
+impl Analyzer {
+    pub fn analyze(&mut self, samples: &Samples) -> Spectrum {
+        // Zero padded at the start until there's enough to fill the window.
+        let padding = FFT_SIZE.saturating_sub(samples.recent.len());
+        self.input[..padding].fill(0.0);
+        for (slot, sample) in self.input[padding..].iter_mut().zip(&samples.recent) {
+            *slot = *sample;
+        }
+        let scope = downsample(&self.input, SCOPE_POINTS);
+
+        for (sample, weight) in self.input.iter_mut().zip(&self.window) {
+            *sample *= weight;
+        }
+        if self
+            .fft
+            .process_with_scratch(&mut self.input, &mut self.output, &mut self.scratch)
+            .is_err()
+        {
+            return Spectrum::default();
+        }
+
+        // The window halves the amplitude, so a full scale sine comes out at 0dB.
+        let scale = 4.0 / FFT_SIZE as f32;
+        let bands: Vec<f32> = band_bins(samples.rate, BANDS)
+            .into_iter()
+            .zip(&self.previous)
+            .map(|((start, end), previous)| {
+                let peak = self.output[start..end]
+                    .iter()
+                    .map(|bin| bin.norm() * scale)
+                    .fold(0.0, f32::max);
+                let level =
+                    ((20.0 * peak.max(1e-9).log10() - FLOOR_DB) / -FLOOR_DB).clamp(0.0, 1.0);
+                level.max(previous * FALLOFF)
+            })
+            .collect();
+        self.previous.clone_from(&bands);
+        Spectrum { bands, scope }
+    }
+}
+
+// The range of FFT bins every band covers. Low bands are narrower than a bin at this size,
+// those get the bin they fall in.
+pub(crate) fn band_bins(rate: u32, count: usize) -> Vec<(usize, usize)> {
+    let bins = FFT_SIZE / 2 + 1;
+    let highest = HIGHEST.min(rate as f32 / 2.0).max(LOWEST * 2.0);
+    let bin_width = rate.max(1) as f32 / FFT_SIZE as f32;
+    let bin = |frequency: f32| ((frequency / bin_width) as usize).clamp(1, bins - 1);
+    (0..count)
+        .map(|band| {
+            let edge = |band: usize| LOWEST * (highest / LOWEST).powf(band as f32 / count as f32);
+            let start = bin(edge(band));
+            let end = bin(edge(band + 1)).max(start + 1);
+            (start, end)
+        })
+        .collect()
+}
+
+// Every nth sample, so the whole window fits the oscilloscope.
+fn downsample(samples: &[f32], points: usize) -> Vec<f32> {
+    let step = (samples.len() / points).max(1);
+    samples
+        .iter()
+        .step_by(step)
+        .take(points)
+        .map(|sample| sample.clamp(-1.0, 1.0))
+        .collect()
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,35 @@
+use super::*;
+
+#[test]
+fn test_sine_lands_in_its_band() {
+    let rate = 44_100;
+    let recent = (0..FFT_SIZE)
+        .map(|i| (2.0 * PI * 1_000.0 * i as f32 / rate as f32).sin())
+        .collect();
+    let mut analyzer = Analyzer::default();
+    let spectrum = analyzer.analyze(&Samples { rate, recent });
+
+    let bins = band_bins(rate, BANDS);
+    let bin = (1_000.0 / (rate as f32 / FFT_SIZE as f32)).round() as usize;
+    let band = bins
+        .iter()
+        .position(|(start, end)| (*start..*end).contains(&bin))
+        .unwrap();
+    let loudest = (0..BANDS)
+        .max_by(|a, b| spectrum.bands[*a].total_cmp(&spectrum.bands[*b]))
+        .unwrap();
+    assert_eq!(loudest, band);
+    assert!(spectrum.bands[band] > 0.9);
+    assert!(spectrum.bands[0] < 0.5);
+    assert_eq!(spectrum.scope.len(), SCOPE_POINTS);
+}
+
+#[test]
+fn test_band_bins_cover_the_range() {
+    let bins = band_bins(44_100, BANDS);
+    assert_eq!(bins.len(), BANDS);
+    assert!(bins.iter().all(|(start, end)| start < end));
+    assert!(bins.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    // 16kHz at a 2048 point FFT.
+    assert_eq!(bins[BANDS - 1].1, 743);
+}
@@ -1,62 +1,220 @@
 use crate::Audio;
 use crate::Command;
 use crate::Config;
-use crate::order::Order;
+use crate::browse::{BrowseRoot, Browser};
+use crate::cover::CoverCache;
+use crate::fuzzy_search::SearchIndex;
+use crate::history::{self, History, PlayThreshold};
+use crate::lyrics::LyricsCache;
+use crate::order::{Order, SortPreset, builtin_presets};
 use crate::playback::SinkState;
-use crate::utility::load_audio;
+use crate::playlist::Playlists;
+use crate::queue::{Queue, Repeat};
+use crate::scrobbler::ScrobbleLog;
+use crate::spectrum::Visualizer;
+use crate::stats::{Stats, Weights};
+use crate::theme::{Theme, load_themes};
+use crate::utility::{config_dir, load_audio, order_by};
+use crate::waveform::{self, Waveforms};
 use ratatui::widgets::ListState;
 use ratatui::widgets::TableState;
+use std::cell::RefCell;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Instant;
 
 const DEFAULT_SEEK_DISTANCE: usize = 5;
 
 pub(crate) struct PlayerState {
     pub tracks: Vec<Audio>,
+    pub library_path: PathBuf,
     pub is_searching: bool,
     pub is_adjusting: bool,
     pub is_configuring: bool,
     pub is_choosing: bool,
+    pub is_browsing: bool,
+    pub is_viewing_queue: bool,
+    pub is_moving_entry: bool,
+    pub is_picking_result: bool,
+    pub is_viewing_history: bool,
+    pub is_viewing_playlists: bool,
+    pub is_naming_playlist: bool,
+    pub is_viewing_lyrics: bool,
     pub keyword: String,
     pub current_track_index: Option<usize>,
     pub table_state: TableState,
     pub list_state: ListState,
+    pub queue_state: ListState,
+    pub search_state: TableState,
+    pub history_state: ListState,
+    pub playlist_state: ListState,
     pub tx: Sender<Command>,
     pub sink_rx: Receiver<SinkState>,
     pub number_of_tracks: usize,
     pub _sink_state: Option<SinkState>,
-    pub matched_tracks: Vec<Audio>,
+    pub matched_tracks: Vec<usize>,
+    pub search_index: SearchIndex,
     pub iteration_count: usize,
     pub volume: f32,
     pub playback_order: Order,
     pub seek_distance: usize,
+    pub browser: Browser,
+    // Positions in `tracks`, in the order the library table lists them.
+    pub library: Vec<usize>,
+    pub queue: Queue,
+    pub repeat: Repeat,
+    pub stats: Stats,
+    pub sort_presets: Vec<SortPreset>,
+    pub write_ratings: bool,
+    pub history: History,
+    // Positions in `tracks` and when they were played, as listed on the history screen.
+    pub recently_played: Vec<(usize, u64)>,
+    pub scrobble_log: ScrobbleLog,
+    pub playlists: Playlists,
+    // The playlist whose tracks are listed, None lists the playlists themselves.
+    pub opened_playlist: Option<usize>,
+    // What is being typed as a playlist name, and which playlist it renames.
+    pub playlist_name: String,
+    pub renamed_playlist: Option<usize>,
+    // Shown under the playlists, e.g. where one was exported to.
+    pub playlist_notice: String,
+    // Filled while rendering, which only gets to borrow the state.
+    pub covers: RefCell<CoverCache>,
+    pub lyrics: RefCell<LyricsCache>,
+    // How far unsynced lyrics are scrolled down, synced ones follow the track.
+    pub lyrics_scroll: usize,
+    pub visualizer: Visualizer,
+    pub waveforms: Waveforms,
+    // The progress label counts down instead of up.
+    pub show_remaining: bool,
+    // Long titles scroll by the time since this.
+    pub marquee_clock: Instant,
+    pub themes: Vec<Theme>,
+    pub theme: Theme,
 }
 
 impl PlayerState {
-    fn init(track_path: PathBuf, seek_distance: usize) -> Self {
+    fn init(config: Config) -> Self {
+        let (_, tracks, playlist_files) = load_audio(config.path.clone());
+        PlayerState::new(config, tracks, &playlist_files, config_dir())
+    }
+
+    // A player over the given tracks that reads and writes nothing on disk.
+    #[cfg(test)]
+    pub(crate) fn detached(tracks: Vec<Audio>) -> Self {
+        PlayerState::new(Config::default(), tracks, &[], None)
+    }
+
+    // `dir` holds the stats, history, playlists and waveforms, they start empty without it.
+    fn new(
+        config: Config,
+        tracks: Vec<Audio>,
+        playlist_files: &[PathBuf],
+        dir: Option<PathBuf>,
+    ) -> Self {
+        let track_path = config.path;
         let (tx, _rx) = mpsc::channel::<Command>();
         let (_tx, sink_rx) = mpsc::channel::<SinkState>();
-        let (number_of_tracks, tracks) = load_audio(track_path);
+        let number_of_tracks = tracks.len();
+        let search_index = SearchIndex::build(&tracks);
+        let browser = Browser::open(BrowseRoot::Artists, &tracks, &track_path);
+        let (stats, history, mut playlists, waveforms) = match dir {
+            Some(dir) => (
+                Stats::load(dir.join("stats.toml"), config.weights),
+                History::load(dir.join(history::FILE_NAME), config.play_threshold),
+                Playlists::load(dir.join("playlists.toml")),
+                Waveforms::load(dir.join(waveform::FILE_NAME)),
+            ),
+            None => (
+                Stats::default(),
+                History::default(),
+                Playlists::default(),
+                Waveforms::default(),
+            ),
+        };
+        playlists.import(playlist_files, &tracks);
+        playlists.smart = config.smart_playlist;
+        playlists.refresh_smart(&tracks, &stats);
+        let scrobble_log = ScrobbleLog::new(
+            config
+                .scrobble_log
+                .unwrap_or_else(|| track_path.join(".scrobbler.log")),
+        );
+        let themes = load_themes(&config.custom_theme);
+        let theme = match config.theme {
+            Some(name) => themes
+                .iter()
+                .find(|theme| theme.name == name)
+                .cloned()
+                .unwrap_or_else(|| {
+                    eprintln!("\nNo theme called {name}, using the default one.");
+                    Theme::default()
+                }),
+            None => Theme::default(),
+        };
+        let mut sort_presets = builtin_presets();
+        sort_presets.extend(config.sort);
+        let playback_order = Order::Sort(SortPreset::artist());
+        let mut library: Vec<usize> = (0..tracks.len()).collect();
+        order_by(&playback_order, &mut library, &tracks, &stats);
         PlayerState {
             tracks,
+            library_path: track_path,
             number_of_tracks,
             is_searching: false,
             is_adjusting: false,
             is_configuring: false,
             is_choosing: true,
+            is_browsing: false,
+            is_viewing_queue: false,
+            is_moving_entry: false,
+            is_picking_result: false,
+            is_viewing_history: false,
+            is_viewing_playlists: false,
+            is_naming_playlist: false,
+            is_viewing_lyrics: false,
             keyword: String::new(),
             current_track_index: None,
             table_state: TableState::default(),
             list_state: ListState::default(),
+            queue_state: ListState::default(),
+            search_state: TableState::default(),
+            history_state: ListState::default(),
+            playlist_state: ListState::default(),
             tx,
             sink_rx,
             _sink_state: None,
             matched_tracks: Vec::new(),
+            search_index,
             iteration_count: 0,
             volume: 1.0,
-            playback_order: Order::Artist,
-            seek_distance,
+            playback_order,
+            seek_distance: config.seek_distance,
+            browser,
+            library,
+            queue: Queue::default(),
+            repeat: Repeat::All,
+            stats,
+            sort_presets,
+            write_ratings: config.write_ratings,
+            history,
+            recently_played: Vec::new(),
+            scrobble_log,
+            playlists,
+            opened_playlist: None,
+            playlist_name: String::new(),
+            renamed_playlist: None,
+            playlist_notice: String::new(),
+            covers: RefCell::new(CoverCache::default()),
+            lyrics: RefCell::new(LyricsCache::default()),
+            lyrics_scroll: 0,
+            visualizer: Visualizer::default(),
+            waveforms,
+            show_remaining: false,
+            marquee_clock: Instant::now(),
+            themes,
+            theme,
         }
     }
 
@@ -74,7 +232,7 @@ impl PlayerState {
 impl Configure for PlayerState {
     fn configured(path: PathBuf) -> PlayerState {
         let config = PlayerState::load_config(&path);
-        PlayerState::init(config.path, config.seek_distance)
+        PlayerState::init(config)
     }
 }
 
@@ -86,9 +244,21 @@ pub(crate) trait Configure {
 // TODO: Handle the case where you don't find any music in the default path.
 impl Default for PlayerState {
     fn default() -> Self {
-        match home::home_dir() {
-            Some(path) => PlayerState::init(path.join("Music"), DEFAULT_SEEK_DISTANCE),
-            None => PlayerState::init(PathBuf::from("/home"), DEFAULT_SEEK_DISTANCE)
-        }
+        let path = match home::home_dir() {
+            Some(path) => path.join("Music"),
+            None => PathBuf::from("/home"),
+        };
+        PlayerState::init(Config {
+            path,
+            seek_distance: DEFAULT_SEEK_DISTANCE,
+            weights: Weights::default(),
+            sort: Vec::new(),
+            write_ratings: false,
+            play_threshold: PlayThreshold::default(),
+            smart_playlist: Vec::new(),
+            scrobble_log: None,
+            theme: None,
+            custom_theme: Vec::new(),
+        })
     }
 }
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize, Serializer};
+
+const DAY: f64 = 24.0 * 60.0 * 60.0;
+// Rewriting stats.toml is slow on an SD card, so changes are gathered up for this long and
+// written together.
+const SAVE_DELAY: Duration = Duration::from_secs(30);
+
+// How much each part of a track's history counts towards the weighted shuffle, set under
+// `[weights]` in daph.toml. Zero switches a part off.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub(crate) struct Weights {
+    // Per star above or below an unrated three.
+    pub rating: f64,
+    // Per month since the track was last heard, capped at a year.
+    pub unheard: f64,
+    // Per skip, fading out over a month after the last one.
+    pub skip: f64,
+    // Per doubling of the play count.
+    pub play_count: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            rating: 1.0,
+            unheard: 0.5,
+            skip: 1.0,
+            play_count: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct TrackStats {
+    pub play_count: u32,
+    pub skip_count: u32,
+    pub last_played: Option<u64>,
+    pub last_skipped: Option<u64>,
+    // One to five stars.
+    pub rating: Option<u8>,
+    pub favourite: bool,
+}
+
+// What daph remembers about every track, kept in stats.toml next to daph.toml.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Stats {
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+    #[serde(skip)]
+    pub weights: Weights,
+    // When the oldest change that isn't on disk yet was made.
+    #[serde(skip)]
+    pub changed: Option<Instant>,
+    #[serde(default, serialize_with = "utf8_paths")]
+    pub tracks: HashMap<PathBuf, TrackStats>,
+}
+
+// TOML keys have to be strings, so tracks whose path isn't valid UTF-8 are left out instead of
+// failing the whole file.
+fn utf8_paths<S: Serializer>(
+    tracks: &HashMap<PathBuf, TrackStats>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(
+        tracks
+            .iter()
+            .filter_map(|(path, stats)| Some((path.to_str()?, stats))),
+    )
+}
+
+impl Stats {
+    pub fn load(path: PathBuf, weights: Weights) -> Self {
+        let mut stats: Stats = match fs::read_to_string(&path) {
+            Ok(file) => toml::from_str(&file).unwrap_or_else(|_| {
+                eprintln!("\nCan't read the stats, starting over: {}", path.display());
+                Stats::default()
+            }),
+            Err(_) => Stats::default(),
+        };
+        stats.path = Some(path);
+        stats.weights = weights;
+        stats
+    }
+
+    pub fn save(&mut self) {
+        self.changed = None;
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        match toml::to_string(self) {
+            Ok(file) => fs::write(path, file).unwrap_or(()),
+            Err(_) => log::warn!("Can't write the stats: {}", path.display()),
+        }
+    }
+
+    // Called every iteration of the main loop, the rest is saved on exit.
+    pub fn save_if_due(&mut self) {
+        if self
+            .changed
+            .is_some_and(|changed| changed.elapsed() >= SAVE_DELAY)
+        {
+            self.save();
+        }
+    }
+
+    fn mark_changed(&mut self) {
+        self.changed.get_or_insert_with(Instant::now);
+    }
+
+    pub fn get(&self, path: &Path) -> TrackStats {
+        self.tracks.get(path).cloned().unwrap_or_default()
+    }
+
+    pub fn record_play(&mut self, path: &Path) {
+        let track = self.tracks.entry(path.to_path_buf()).or_default();
+        track.play_count += 1;
+        track.last_played = Some(now());
+        self.mark_changed();
+    }
+
+    pub fn record_skip(&mut self, path: &Path) {
+        let track = self.tracks.entry(path.to_path_buf()).or_default();
+        track.skip_count += 1;
+        track.last_skipped = Some(now());
+        self.mark_changed();
+    }
+
+    pub fn set_rating(&mut self, path: &Path, rating: Option<u8>) {
+        let track = self.tracks.entry(path.to_path_buf()).or_default();
+        track.rating = rating.map(|stars| stars.clamp(1, 5));
+        self.mark_changed();
+    }
+
+    // Returns whether the track is a favourite now.
+    pub fn toggle_favourite(&mut self, path: &Path) -> bool {
+        let track = self.tracks.entry(path.to_path_buf()).or_default();
+        track.favourite = !track.favourite;
+        let favourite = track.favourite;
+        self.mark_changed();
+        favourite
+    }
+
+    // Always above zero, so every track keeps a chance to come up.
+    pub fn weight(&self, path: &Path, now: u64) -> f64 {
+        let track = self.get(path);
+        let weights = &self.weights;
+        let days_since = |time: Option<u64>| time.map(|time| now.saturating_sub(time) as f64 / DAY);
+
+        let stars = track.rating.unwrap_or(3) as f64;
+        let rating = 1.0 + weights.rating * (stars - 3.0) / 2.0;
+
+        let unheard = days_since(track.last_played).unwrap_or(365.0).min(365.0);
+        let unheard = 1.0 + weights.unheard * unheard / 30.0;
+
+        let fading = days_since(track.last_skipped).map_or(0.0, |days| (-days / 30.0).exp());
+        let skip = 1.0 + weights.skip * track.skip_count as f64 * fading;
+
+        let plays = 1.0 + weights.play_count * (1.0 + track.play_count as f64).log2();
+
+        (rating * unheard / skip / plays).max(0.01)
+    }
+}
+
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,97 @@
+use super::*;
+
+#[test]
+fn test_weight() {
+    let now = 1_000 * DAY as u64;
+    let mut stats = Stats::default();
+    let path = |name: &str| PathBuf::from(name);
+
+    stats.tracks.insert(
+        path("loved"),
+        TrackStats {
+            rating: Some(5),
+            last_played: Some(now),
+            ..Default::default()
+        },
+    );
+    stats.tracks.insert(
+        path("skipped"),
+        TrackStats {
+            skip_count: 4,
+            last_played: Some(now),
+            last_skipped: Some(now),
+            ..Default::default()
+        },
+    );
+    stats.tracks.insert(
+        path("heard"),
+        TrackStats {
+            last_played: Some(now),
+            ..Default::default()
+        },
+    );
+
+    let heard = stats.weight(&path("heard"), now);
+    assert!(stats.weight(&path("loved"), now) > heard);
+    assert!(stats.weight(&path("skipped"), now) < heard);
+    assert!(stats.weight(&path("never played"), now) > heard);
+}
+
+#[test]
+fn test_round_trip() {
+    let mut stats = Stats::default();
+    stats.tracks.insert(
+        PathBuf::from("/music/toe/Goodbye.flac"),
+        TrackStats {
+            play_count: 3,
+            rating: Some(4),
+            ..Default::default()
+        },
+    );
+
+    let file = toml::to_string(&stats).unwrap();
+    let loaded: Stats = toml::from_str(&file).unwrap();
+    assert_eq!(loaded.tracks, stats.tracks);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_non_utf8_paths_are_left_out() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut stats = Stats::default();
+    stats.record_play(Path::new(OsStr::from_bytes(b"/music/caf\xe9.flac")));
+    let goodbye = Path::new("/music/toe/Goodbye.flac");
+    stats.record_play(goodbye);
+
+    let file = toml::to_string(&stats).unwrap();
+    let loaded: Stats = toml::from_str(&file).unwrap();
+    assert_eq!(loaded.tracks.len(), 1);
+    assert_eq!(loaded.get(goodbye).play_count, 1);
+}
+
+#[test]
+fn test_rating_and_favourite() {
+    let mut stats = Stats::default();
+    let path = PathBuf::from("/music/toe/Goodbye.flac");
+
+    stats.set_rating(&path, Some(9));
+    assert_eq!(stats.get(&path).rating, Some(5));
+    stats.set_rating(&path, None);
+    assert_eq!(stats.get(&path).rating, None);
+
+    assert!(stats.toggle_favourite(&path));
+    assert!(!stats.toggle_favourite(&path));
+}
+
+#[test]
+fn test_saves_wait() {
+    let mut stats = Stats::default();
+    stats.record_play(Path::new("/music/toe/Goodbye.flac"));
+    stats.save_if_due();
+    assert!(stats.changed.is_some());
+
+    stats.save();
+    assert!(stats.changed.is_none());
+}
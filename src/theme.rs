@@ -0,0 +1,200 @@
+use std::str::FromStr;
+
+use ratatui::style::palette::tailwind;
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+
+// Every colour the screens use, by what it's for.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Theme {
+    pub name: String,
+    // Most text and borders, and the player while it plays.
+    pub foreground: Style,
+    // Selections, settings that are on, the played part of the progress bar.
+    pub accent: Style,
+    // The playing track wherever it's listed.
+    pub playing: Style,
+    // The player while it's paused.
+    pub paused: Style,
+    // Details next to the main text, like artists and play times.
+    pub muted: Style,
+    // Behind the progress bar.
+    pub bar: Style,
+    // The played part of the progress bar and the visualizer, then the part still to come.
+    pub progress: Style,
+    pub remaining: Style,
+    // The search and playlist name boxes.
+    pub input: Style,
+    // Album art only makes a mess of 1-bit displays.
+    pub covers: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            name: String::from("default"),
+            foreground: Style::new().fg(Color::Yellow),
+            accent: Style::new().fg(Color::Green),
+            playing: Style::new()
+                .fg(tailwind::SKY.c200)
+                .add_modifier(Modifier::BOLD),
+            paused: Style::new().fg(Color::Magenta),
+            muted: Style::new().fg(Color::DarkGray),
+            bar: Style::new().bg(Color::DarkGray),
+            progress: Style::new().fg(Color::Green),
+            remaining: Style::new().fg(Color::Gray),
+            input: Style::new().fg(Color::Green),
+            covers: true,
+        }
+    }
+}
+
+// The default one first, as daph starts with it unless daph.toml picks another.
+pub(crate) fn builtin_themes() -> Vec<Theme> {
+    vec![
+        Theme::default(),
+        // Bright and bold, readable on a screen in the sun.
+        Theme {
+            name: String::from("high-contrast"),
+            foreground: Style::new().fg(Color::White).add_modifier(Modifier::BOLD),
+            accent: Style::new()
+                .fg(Color::Black)
+                .bg(Color::LightYellow)
+                .add_modifier(Modifier::BOLD),
+            playing: Style::new()
+                .fg(Color::LightCyan)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            paused: Style::new()
+                .fg(Color::LightMagenta)
+                .add_modifier(Modifier::BOLD),
+            muted: Style::new().fg(Color::Gray),
+            bar: Style::new().bg(Color::Black),
+            progress: Style::new().fg(Color::LightGreen),
+            remaining: Style::new().fg(Color::White),
+            input: Style::new()
+                .fg(Color::LightYellow)
+                .add_modifier(Modifier::BOLD),
+            covers: true,
+        },
+        // No colour at all, only bold, dim and reversed text.
+        Theme {
+            name: String::from("monochrome"),
+            foreground: Style::new(),
+            accent: Style::new().add_modifier(Modifier::REVERSED),
+            playing: Style::new().add_modifier(Modifier::BOLD),
+            paused: Style::new().add_modifier(Modifier::DIM),
+            muted: Style::new().add_modifier(Modifier::DIM),
+            bar: Style::new(),
+            progress: Style::new(),
+            remaining: Style::new().add_modifier(Modifier::DIM),
+            input: Style::new().add_modifier(Modifier::BOLD),
+            covers: false,
+        },
+    ]
+}
+
+// A theme of one's own in daph.toml, anything left out comes from `base`:
+//
+// [[custom_theme]]
+// name = "night"
+// base = "high-contrast"
+// foreground = "#ffb000"
+// accent = "black on #ffb000 bold"
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct CustomTheme {
+    pub name: String,
+    pub base: Option<String>,
+    pub foreground: Option<StyleSpec>,
+    pub accent: Option<StyleSpec>,
+    pub playing: Option<StyleSpec>,
+    pub paused: Option<StyleSpec>,
+    pub muted: Option<StyleSpec>,
+    pub bar: Option<StyleSpec>,
+    pub progress: Option<StyleSpec>,
+    pub remaining: Option<StyleSpec>,
+    pub input: Option<StyleSpec>,
+    pub covers: Option<bool>,
+}
+
+impl CustomTheme {
+    pub fn build(&self, themes: &[Theme]) -> Theme {
+        let base = self
+            .base
+            .as_deref()
+            .and_then(|base| themes.iter().find(|theme| theme.name == base))
+            .cloned()
+            .unwrap_or_default();
+        let style = |spec: &Option<StyleSpec>, base: Style| spec.map_or(base, |spec| spec.0);
+        Theme {
+            name: self.name.clone(),
+            foreground: style(&self.foreground, base.foreground),
+            accent: style(&self.accent, base.accent),
+            playing: style(&self.playing, base.playing),
+            paused: style(&self.paused, base.paused),
+            muted: style(&self.muted, base.muted),
+            bar: style(&self.bar, base.bar),
+            progress: style(&self.progress, base.progress),
+            remaining: style(&self.remaining, base.remaining),
+            input: style(&self.input, base.input),
+            covers: self.covers.unwrap_or(base.covers),
+        }
+    }
+}
+
+// The built-in themes followed by the custom ones, which may build on any theme before them.
+// One with a built-in name takes its place.
+pub(crate) fn load_themes(custom: &[CustomTheme]) -> Vec<Theme> {
+    let mut themes = builtin_themes();
+    for theme in custom {
+        let theme = theme.build(&themes);
+        match themes.iter().position(|other| other.name == theme.name) {
+            Some(position) => themes[position] = theme,
+            None => themes.push(theme),
+        }
+    }
+    themes
+}
+
+// "yellow", "#ffb000 bold" or "black on light-yellow reversed". Colours are ratatui's names,
+// hex values or 256 colour indexes, the modifiers are bold, dim, italic, underlined and
+// reversed.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub(crate) struct StyleSpec(pub Style);
+
+impl TryFrom<String> for StyleSpec {
+    type Error = String;
+
+    fn try_from(spec: String) -> Result<Self, Self::Error> {
+        let mut style = Style::new();
+        let mut words = spec.split_whitespace();
+        while let Some(word) = words.next() {
+            let modifier = match word.to_lowercase().as_str() {
+                "bold" => Modifier::BOLD,
+                "dim" => Modifier::DIM,
+                "italic" => Modifier::ITALIC,
+                "underlined" => Modifier::UNDERLINED,
+                "reversed" => Modifier::REVERSED,
+                "on" => {
+                    let Some(Ok(color)) = words.next().map(Color::from_str) else {
+                        return Err(format!("Missing background colour in style: {spec}"));
+                    };
+                    style = style.bg(color);
+                    continue;
+                }
+                _ => match Color::from_str(word) {
+                    Ok(color) => {
+                        style = style.fg(color);
+                        continue;
+                    }
+                    Err(_) => return Err(format!("Unknown colour in style: {spec}")),
+                },
+            };
+            style = style.add_modifier(modifier);
+        }
+        Ok(StyleSpec(style))
+    }
+}
+
+#[cfg(test)]
+mod test;
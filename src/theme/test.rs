@@ -0,0 +1,45 @@
+use super::*;
+
+#[test]
+fn test_style_spec() {
+    let spec = |text: &str| StyleSpec::try_from(String::from(text)).map(|spec| spec.0);
+    assert_eq!(spec("yellow"), Ok(Style::new().fg(Color::Yellow)));
+    assert_eq!(
+        spec("black on #ffb000 bold"),
+        Ok(Style::new()
+            .fg(Color::Black)
+            .bg(Color::Rgb(0xff, 0xb0, 0x00))
+            .add_modifier(Modifier::BOLD))
+    );
+    assert_eq!(spec(""), Ok(Style::new()));
+    assert!(spec("blurple").is_err());
+    assert!(spec("white on").is_err());
+}
+
+#[test]
+fn test_custom_theme() {
+    let config: toml::Table = toml::from_str(
+        r##"
+        [[custom_theme]]
+        name = "night"
+        base = "monochrome"
+        accent = "#ffb000"
+
+        [[custom_theme]]
+        name = "default"
+        covers = false
+        "##,
+    )
+    .unwrap();
+    let custom: Vec<CustomTheme> = config["custom_theme"].clone().try_into().unwrap();
+    let themes = load_themes(&custom);
+
+    let names: Vec<&str> = themes.iter().map(|theme| theme.name.as_str()).collect();
+    assert_eq!(names, ["default", "high-contrast", "monochrome", "night"]);
+    let night = &themes[3];
+    assert_eq!(night.accent, Style::new().fg(Color::Rgb(0xff, 0xb0, 0x00)));
+    assert_eq!(night.playing, builtin_themes()[2].playing);
+    assert!(!night.covers);
+    assert!(!themes[0].covers);
+    assert_eq!(themes[0].foreground, Theme::default().foreground);
+}
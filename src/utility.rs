@@ -1,53 +1,87 @@
 use super::*;
+use crate::browse::Browser;
 use crate::order::Order;
+use crate::playlist::formats::is_playlist_file;
+use crate::queue::Repeat;
+use crate::stats::{Stats, now};
 use rand::Rng;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Instant, UNIX_EPOCH};
 use walkdir::WalkDir;
 
 use lofty::read_from_path;
 
+use lofty::config::WriteOptions;
 use lofty::file::{AudioFile, TaggedFileExt};
-use lofty::tag::Accessor;
+use lofty::id3::v2::PopularimeterFrame;
+use lofty::tag::{Accessor, ItemKey, ItemValue, TagExt, TagItem, TagType};
 
-pub(crate) fn load_audio(path: PathBuf) -> (usize, Vec<Audio>) {
+// Also returns the playlist files it came across, so they can be imported.
+pub(crate) fn load_audio(path: PathBuf) -> (usize, Vec<Audio>, Vec<PathBuf>) {
     let mut tracks = Vec::new();
+    let mut playlist_files = Vec::new();
     for entry in WalkDir::new(path) {
         match entry {
             Ok(entry) => {
-                if let Some(extension) = entry.path().extension() {
-                    if extension == "mp3" || extension == "flac" {
-                        let path = entry.path();
-                        let tagged_file = match read_from_path(path) {
-                            Ok(it) => it,
-                            Err(_) => {
-                                eprintln!("\nCan't read the file: {}", path.display());
-                                continue;
-                            }
-                        };
-
-                        let tag = match tagged_file.primary_tag() {
-                            Some(primary_tag) => primary_tag,
-                            None => {
-                                eprintln!("\nGiven file has no readable tags: {}", path.display());
-                                continue;
-                            }
-                        };
-
-                        let tag_title = tag.title();
-                        let tag_artist = tag.artist();
-                        let duration = tagged_file.properties().duration();
-
-                        let title = String::from(tag_title.as_deref().unwrap_or("None"));
-                        let artist = String::from(tag_artist.as_deref().unwrap_or("None"));
-                        let seconds = duration.as_secs();
-
-                        tracks.push(Audio {
-                            is_playing: (false),
-                            name: title,
-                            author: artist,
-                            length: seconds,
-                            path: path.to_path_buf(),
-                        });
-                    }
+                if is_playlist_file(entry.path()) {
+                    playlist_files.push(entry.path().to_path_buf());
+                    continue;
+                }
+                if let Some(extension) = entry.path().extension()
+                    && (extension == "mp3" || extension == "flac")
+                {
+                    let path = entry.path();
+                    let tagged_file = match read_from_path(path) {
+                        Ok(it) => it,
+                        Err(_) => {
+                            eprintln!("\nCan't read the file: {}", path.display());
+                            continue;
+                        }
+                    };
+
+                    let tag = match tagged_file.primary_tag() {
+                        Some(primary_tag) => primary_tag,
+                        None => {
+                            eprintln!("\nGiven file has no readable tags: {}", path.display());
+                            continue;
+                        }
+                    };
+
+                    let tag_title = tag.title();
+                    let tag_artist = tag.artist();
+                    let tag_album = tag.album();
+                    let tag_genre = tag.genre();
+                    let duration = tagged_file.properties().duration();
+
+                    let title = String::from(tag_title.as_deref().unwrap_or("None"));
+                    let artist = String::from(tag_artist.as_deref().unwrap_or("None"));
+                    let album = String::from(tag_album.as_deref().unwrap_or("None"));
+                    let genre = String::from(tag_genre.as_deref().unwrap_or("None"));
+                    let seconds = duration.as_secs();
+                    let added = entry
+                        .metadata()
+                        .ok()
+                        .and_then(|metadata| metadata.modified().ok())
+                        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                        .map(|time| time.as_secs())
+                        .unwrap_or(0);
+
+                    tracks.push(Audio {
+                        is_playing: (false),
+                        name: title,
+                        author: artist,
+                        album,
+                        genre,
+                        year: tag.year(),
+                        track_number: tag.track(),
+                        length: seconds,
+                        added,
+                        musicbrainz_id: tag
+                            .get_string(&ItemKey::MusicBrainzRecordingId)
+                            .map(String::from),
+                        path: path.to_path_buf(),
+                    });
                 }
             }
             Err(_) => eprintln!(
@@ -56,61 +90,311 @@ pub(crate) fn load_audio(path: PathBuf) -> (usize, Vec<Audio>) {
             ),
         }
     }
-    (tracks.len(), tracks)
+    (tracks.len(), tracks, playlist_files)
 }
 
-pub(crate) fn order_by(new: &Order, old: &Order, tracks: &mut [Audio]) -> Option<usize> {
-    if new == old {
-        return None;
+// Sorts the library table, `library` holds positions in `tracks`.
+pub(crate) fn order_by(order: &Order, library: &mut [usize], tracks: &[Audio], stats: &Stats) {
+    // Shuffling is left to the queue, the table keeps its order.
+    if let Order::Sort(preset) = order {
+        library.sort_by(|a, b| preset.compare(&tracks[*a], &tracks[*b], stats));
     }
-    match new {
-        Order::Shuffle => order_shuffle(tracks),
-        Order::Album => order_album(tracks),
-        Order::Artist => order_artist(tracks),
-        Order::Track => order_tracks(tracks),
+}
+
+pub(crate) fn shuffle_by(order: &Order, items: &mut Vec<usize>, tracks: &[Audio], stats: &Stats) {
+    match order {
+        Order::SmartShuffle => order_smart_shuffle(items, tracks),
+        Order::AlbumShuffle => order_album_shuffle(items, tracks),
+        Order::WeightedShuffle => order_weighted_shuffle(items, tracks, stats),
+        _ => order_shuffle(items),
     }
+}
 
-    for index in 0..tracks.len() {
-        if tracks[index].is_playing {
-            return Some(index);
-        }
+pub(crate) fn order_shuffle<T>(tracks: &mut [T]) {
+    // Fisher-Yate Algorithm
+    let size: usize = tracks.len();
+    let mut rng = rand::rng();
+    for i in 0..size {
+        let j = rng.random_range(i..size);
+        tracks.swap(i, j);
     }
-    None
 }
 
-fn order_tracks(tracks: &mut [Audio]) {
-    tracks.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+// Spreads every artist evenly over the whole queue, and every album over its artist's share.
+fn order_smart_shuffle(items: &mut Vec<usize>, tracks: &[Audio]) {
+    let artists = group_by(items, |index| tracks[index].author.to_lowercase())
+        .into_iter()
+        .map(|artist| {
+            let mut albums = group_by(&artist, |index| tracks[index].album.to_lowercase());
+            for album in albums.iter_mut() {
+                order_shuffle(album);
+            }
+            spread(albums)
+        })
+        .collect();
+    *items = spread(artists);
 }
 
-fn order_artist(tracks: &mut [Audio]) {
-    tracks.sort_by(|a, b| {
-        let artist_cmp = a.author.to_lowercase().cmp(&b.author.to_lowercase());
-        if artist_cmp == std::cmp::Ordering::Equal {
-            // If artists are the same, sort by track name
-            a.name.to_lowercase().cmp(&b.name.to_lowercase())
-        } else {
-            artist_cmp
-        }
+// Albums come in a random order, but each one plays start to finish.
+fn order_album_shuffle(items: &mut Vec<usize>, tracks: &[Audio]) {
+    let mut albums = group_by(items, |index| {
+        let track = &tracks[index];
+        (
+            track.album.to_lowercase(),
+            track.path.parent().map(Path::to_path_buf),
+        )
     });
+    for album in albums.iter_mut() {
+        album.sort_by_key(|index| tracks[*index].track_number);
+    }
+    order_shuffle(&mut albums);
+    *items = albums.concat();
 }
 
-fn order_album(_tracks: &mut [Audio]) {}
+// Every track draws a random key raised to 1/weight, heavier tracks tend to draw higher keys
+// and come up earlier (Efraimidis-Spirakis).
+fn order_weighted_shuffle(items: &mut [usize], tracks: &[Audio], stats: &Stats) {
+    let now = now();
+    let mut rng = rand::rng();
+    let mut keys: Vec<(f64, usize)> = items
+        .iter()
+        .map(|index| {
+            let weight = stats.weight(&tracks[*index].path, now);
+            (rng.random::<f64>().powf(1.0 / weight), *index)
+        })
+        .collect();
+    keys.sort_by(|a, b| b.0.total_cmp(&a.0));
+    for (item, (_, index)) in items.iter_mut().zip(keys) {
+        *item = index;
+    }
+}
 
-fn order_shuffle(tracks: &mut [Audio]) {
-    // Fisher-Yate Algorithm
-    let size: usize = tracks.len();
+fn group_by<K, F>(items: &[usize], key: F) -> Vec<Vec<usize>>
+where
+    K: Eq + std::hash::Hash,
+    F: Fn(usize) -> K,
+{
+    let mut groups: HashMap<K, Vec<usize>> = HashMap::new();
+    for item in items {
+        groups.entry(key(*item)).or_default().push(*item);
+    }
+    groups.into_values().collect()
+}
+
+// Gives the members of each group evenly spaced slots between 0 and 1, starting at a random
+// offset with a little jitter, and then merges all groups by slot.
+fn spread(groups: Vec<Vec<usize>>) -> Vec<usize> {
     let mut rng = rand::rng();
-    for i in 0..size {
-        let random_number: u64 = rng.random();
-        let j = random_number as usize % (size - i) + i;
-        tracks.swap(i, j);
+    let mut slots: Vec<(f64, usize)> = Vec::new();
+    for group in groups {
+        let spacing = 1.0 / group.len() as f64;
+        let offset = rng.random::<f64>() * spacing;
+        for (position, item) in group.into_iter().enumerate() {
+            let jitter = (rng.random::<f64>() - 0.5) * 0.1 * spacing;
+            slots.push((position as f64 * spacing + offset + jitter, item));
+        }
     }
+    slots.sort_by(|a, b| a.0.total_cmp(&b.0));
+    slots.into_iter().map(|(_, item)| item).collect()
 }
 
 pub(crate) fn play_new_track(index: usize, state: &mut PlayerState) {
-    state.current_track_index = Some(index);
+    finish_listening(state);
+    if let Some(previous) = state.current_track_index.replace(index) {
+        state.tracks[previous].is_playing = false;
+    }
     state.tracks[index].is_playing = true;
+    state.lyrics_scroll = 0;
+    state.marquee_clock = Instant::now();
+    state.waveforms.request(&state.tracks[index]);
 
     let path = state.tracks[index].path.clone();
+    state.history.start(&path);
     state.tx.send(Command::New(path)).unwrap_or(());
 }
+
+// Writes down how long the current track was listened to, whatever ended it.
+pub(crate) fn finish_listening(state: &mut PlayerState) {
+    let Some(index) = state.current_track_index else {
+        return;
+    };
+    if let Some(listen) = state.history.finish(state.tracks[index].length) {
+        state.scrobble_log.record(&state.tracks[index], &listen);
+        match listen.is_play {
+            true => state.stats.record_play(&listen.path),
+            false => state.stats.record_skip(&listen.path),
+        }
+    }
+}
+
+// Only repeat-all and repeat-one come back around at the ends.
+pub(crate) fn skip(state: &mut PlayerState, forward: bool) {
+    let wrap = state.repeat.wraps();
+    let next = match forward {
+        true => state.queue.next(wrap),
+        false => state.queue.previous(wrap),
+    };
+    if let Some(index) = next {
+        play_new_track(index, state);
+    }
+}
+
+// Called when the current track ran out on its own.
+pub(crate) fn advance(state: &mut PlayerState) {
+    finish_listening(state);
+    match state.repeat {
+        Repeat::One => {
+            if let Some(index) = state.queue.current() {
+                play_new_track(index, state);
+            }
+        }
+        // Only holds for the one track, then it's back to normal.
+        Repeat::StopAfterCurrent => state.repeat = Repeat::Off,
+        Repeat::All | Repeat::Off => skip(state, true),
+    }
+}
+
+// Replaces the queue and starts playing at `start`.
+pub(crate) fn play_queue(tracks: Vec<usize>, start: usize, state: &mut PlayerState) {
+    state.queue.replace(tracks, start);
+    if let Some(index) = state.queue.current() {
+        play_new_track(index, state);
+    }
+}
+
+// Adds to the end of the queue, or right after the current track. With nothing queued yet,
+// the track starts playing.
+pub(crate) fn enqueue(index: usize, play_next: bool, state: &mut PlayerState) {
+    let is_idle = state.queue.cursor.is_none();
+    match play_next {
+        true => state.queue.play_next(index),
+        false => state.queue.append(index),
+    }
+    if is_idle && let Some(index) = state.queue.current() {
+        play_new_track(index, state);
+    }
+}
+
+// Queues the library in table order, or shuffled when that is the playback order.
+pub(crate) fn play_from_library(row: usize, state: &mut PlayerState) {
+    state.queue.replace(state.library.clone(), row);
+    state.queue.is_from_library = true;
+    if state.playback_order.is_shuffle() {
+        let order = &state.playback_order;
+        state
+            .queue
+            .shuffle(|rest| shuffle_by(order, rest, &state.tracks, &state.stats));
+    }
+    if let Some(index) = state.queue.current() {
+        play_new_track(index, state);
+    }
+}
+
+// Rates a track in the stats, and in its tags too when `write_ratings` is set in daph.toml.
+pub(crate) fn rate(index: usize, rating: Option<u8>, state: &mut PlayerState) {
+    let Some(track) = state.tracks.get(index) else {
+        return;
+    };
+    state.stats.set_rating(&track.path, rating);
+    if state.write_ratings && write_rating(&track.path, rating).is_err() {
+        log::warn!("Can't write the rating to: {}", track.path.display());
+    }
+}
+
+// ID3v2 gets a POPM frame on the usual 1-255 scale, Vorbis comments get RATING out of 100
+// and FMPS_RATING out of 1.0, since players disagree on which one they read.
+fn write_rating(path: &Path, rating: Option<u8>) -> lofty::error::Result<()> {
+    let mut tagged_file = read_from_path(path)?;
+    let Some(tag) = tagged_file.primary_tag_mut() else {
+        return Ok(());
+    };
+    let fmps = ItemKey::Unknown(String::from("FMPS_RATING"));
+    tag.remove_key(&ItemKey::Popularimeter);
+    tag.remove_key(&fmps);
+
+    if let Some(stars) = rating {
+        match tag.tag_type() {
+            TagType::Id3v2 => {
+                let popm = [1, 64, 128, 196, 255][stars.clamp(1, 5) as usize - 1];
+                let frame = PopularimeterFrame::new(String::from("daph"), popm, 0);
+                tag.insert(TagItem::new(
+                    ItemKey::Popularimeter,
+                    ItemValue::Binary(frame.as_bytes()?),
+                ));
+            }
+            TagType::VorbisComments => {
+                tag.insert_text(ItemKey::Popularimeter, (stars as u32 * 20).to_string());
+                tag.insert_unchecked(TagItem::new(
+                    fmps,
+                    ItemValue::Text(format!("{:.1}", stars as f64 / 5.0)),
+                ));
+            }
+            _ => {}
+        }
+    }
+    tag.save_to_path(path, WriteOptions::default())
+}
+
+pub(crate) fn remove_track(index: usize, state: &mut PlayerState) {
+    if index >= state.tracks.len() {
+        return;
+    }
+    state.tracks.remove(index);
+    state.search_index.remove(index);
+    state.queue.remove_track(index);
+    state.library.retain(|track| *track != index);
+    for track in state.library.iter_mut() {
+        if *track > index {
+            *track -= 1;
+        }
+    }
+    state.number_of_tracks = state.tracks.len();
+    state.matched_tracks.clear();
+    state.browser = Browser::open(state.browser.root, &state.tracks, &state.library_path);
+
+    state.current_track_index = match state.current_track_index {
+        Some(current) if current == index => None,
+        Some(current) if current > index => Some(current - 1),
+        current => current,
+    };
+}
+
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    home::home_dir().map(|path| path.join(".config").join("daph"))
+}
+
+// 4:07, or 1:02:07 from an hour on.
+pub(crate) fn format_time(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    match hours {
+        0 => format!("{minutes}:{seconds:02}"),
+        hours => format!("{hours}:{minutes:02}:{seconds:02}"),
+    }
+}
+
+// Seconds still to come after the current track. That's the rest of the queue, or the rest
+// of the album when the track was played on its own.
+pub(crate) fn time_after(index: usize, state: &PlayerState) -> u64 {
+    if state.queue.current() == Some(index) {
+        return state
+            .queue
+            .upcoming()
+            .iter()
+            .map(|track| state.tracks[*track].length)
+            .sum();
+    }
+    let current = &state.tracks[index];
+    state
+        .tracks
+        .iter()
+        .filter(|track| {
+            track.album == current.album
+                && track.author == current.author
+                && track.track_number > current.track_number
+        })
+        .map(|track| track.length)
+        .sum()
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,63 @@
+use super::*;
+
+fn track(author: &str, album: &str, number: u32) -> Audio {
+    Audio {
+        author: String::from(author),
+        album: String::from(album),
+        track_number: Some(number),
+        path: PathBuf::from(format!("/music/{author}/{album}/{number}.flac")),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_smart_shuffle_spreads_artists() {
+    let mut tracks = Vec::new();
+    for number in 0..10 {
+        tracks.push(track("toe", "The Book About My Idle Plot", number));
+        tracks.push(track("Adele", "25", number));
+    }
+
+    let mut items: Vec<usize> = (0..tracks.len()).collect();
+    order_smart_shuffle(&mut items, &tracks);
+
+    let mut sorted = items.clone();
+    sorted.sort();
+    assert_eq!(sorted, (0..tracks.len()).collect::<Vec<_>>());
+
+    // Two equally large artists can meet at most twice in a row.
+    let longest_run = items
+        .chunk_by(|a, b| tracks[*a].author == tracks[*b].author)
+        .map(|run| run.len())
+        .max();
+    assert!(longest_run <= Some(2));
+}
+
+#[test]
+fn test_album_shuffle_keeps_albums_together() {
+    let tracks = vec![
+        track("toe", "For Long Tomorrow", 2),
+        track("Adele", "25", 1),
+        track("toe", "For Long Tomorrow", 1),
+        track("Adele", "25", 2),
+        track("Adele", "21", 1),
+    ];
+
+    let mut items: Vec<usize> = (0..tracks.len()).collect();
+    order_album_shuffle(&mut items, &tracks);
+
+    let albums: Vec<Vec<u32>> = items
+        .chunk_by(|a, b| tracks[*a].album == tracks[*b].album)
+        .map(|album| album.iter().filter_map(|i| tracks[*i].track_number).collect())
+        .collect();
+    assert_eq!(albums.len(), 3);
+    assert!(albums.iter().all(|album| album.is_sorted()));
+}
+
+#[test]
+fn test_format_time() {
+    assert_eq!(format_time(0), "0:00");
+    assert_eq!(format_time(187), "3:07");
+    assert_eq!(format_time(3599), "59:59");
+    assert_eq!(format_time(3727), "1:02:07");
+}
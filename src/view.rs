@@ -1,15 +1,18 @@
 use crate::Audio;
 use crate::PlayerState;
 use crate::SinkState;
-use crate::order::Order;
+use crate::menu::{ConfigOption, config_options};
+use crate::queue::Repeat;
+use crate::spectrum::Visualizer;
+use crate::utility::{format_time, time_after};
 use number_drawer::NumberDrawer;
+use profile::Profile;
 use ratatui::Frame;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Flex;
 use ratatui::layout::Rect;
 use ratatui::layout::{Constraint, Layout};
-use ratatui::style::palette::tailwind;
-use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::style::{Color, Style, Stylize};
 use ratatui::text::Line;
 use ratatui::text::Span;
 use ratatui::widgets::Borders;
@@ -20,19 +23,21 @@ use ratatui::widgets::Widget;
 use ratatui::widgets::{Block, BorderType, Padding};
 use ratatui::widgets::{Row, Table};
 use std::time::Duration;
+mod marquee;
 mod number_drawer;
+mod profile;
 mod view_utility;
 
-const CUSTOM_LABEL_COLOR: Color = tailwind::SKY.c200;
-const BY_COLOR: Color = tailwind::RED.c300;
-const GAUGE_COLOR: Color = tailwind::GREEN.c800;
-const PAUSED_COLOR: Color = Color::Gray;
-
 pub(crate) fn render(frame: &mut Frame, state: &PlayerState, sink: &SinkState) {
+    let theme = &state.theme;
+    let profile = Profile::for_area(frame.area());
     let settings = Block::default()
-        .fg(Color::Yellow)
+        .style(theme.foreground)
         .padding(Padding::uniform(2))
-        .padding(Padding::top(4))
+        .padding(Padding::top(match profile {
+            Profile::Compact => 1,
+            _ => 4,
+        }))
         .title("Order")
         .borders(Borders::TOP | Borders::BOTTOM);
 
@@ -46,23 +51,20 @@ pub(crate) fn render(frame: &mut Frame, state: &PlayerState, sink: &SinkState) {
             Style::new()
         };
 
-        let options = [
-            Order::Shuffle.to_string(),
-            Order::Album.to_string(),
-            Order::Artist.to_string(),
-            Order::Track.to_string(),
-        ];
-
         // TODO: This should be inside view_utility.
-        let rows: Vec<Line> = options
+        let rows: Vec<Line> = config_options(&state.sort_presets, &state.themes)
             .iter()
             .map(|item| {
-                let style = match *item == state.playback_order.to_string() {
-                    true => Style::default().fg(Color::Green),
+                let style = match item {
+                    ConfigOption::Order(order) if *order == state.playback_order => theme.accent,
+                    ConfigOption::Repeat(repeat) if *repeat == state.repeat => theme.accent,
+                    ConfigOption::Theme(name) if *name == theme.name => theme.accent,
                     _ => Style::default(),
                 };
 
-                Span::from(item).style(style).into_left_aligned_line()
+                Span::from(item.to_string())
+                    .style(style)
+                    .into_left_aligned_line()
             })
             .collect();
 
@@ -77,101 +79,340 @@ pub(crate) fn render(frame: &mut Frame, state: &PlayerState, sink: &SinkState) {
             string_volume = format!("0{volume}")
         }
 
+        // Big digits when they fit, a plain line on screens too small for them.
         let enlarged_volume = NumberDrawer::draw(&string_volume);
+        let width = enlarged_volume
+            .lines()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or_default() as u16;
+        let height = enlarged_volume.lines().count() as u16;
+        let area = frame.area();
+        let (text, width, height) = match width <= area.width && height <= area.height {
+            true => (enlarged_volume, width, height),
+            false => {
+                let text = format!("Volume {string_volume}");
+                (text.clone(), text.len() as u16, 1)
+            }
+        };
 
-        let centered_area = view_utility::center(
-            frame.area(),
-            Constraint::Percentage(100),
-            Constraint::Percentage(100),
-        );
+        let centered_area =
+            view_utility::center(area, Constraint::Length(width), Constraint::Length(height));
+        let volume_paragraph = Paragraph::new(text).style(theme.foreground);
 
-        let mut spacer = 0;
-        if centered_area.width > 10 && centered_area.height > 10 {
-            spacer = 5;
-        }
+        frame.render_widget(Clear, area);
+        frame.render_widget(volume_paragraph, centered_area);
+    } else if state.is_browsing {
+        // Browse Section
+        frame.render_widget(Clear, frame.area());
+        let [top, bottom] =
+            Layout::vertical([Constraint::Length(2), Constraint::Fill(1)]).areas(frame.area());
 
-        let volume_paragraph = Paragraph::new(enlarged_volume)
-            .style(Style::default().fg(Color::Yellow))
-            .block(Block::new().borders(Borders::NONE).padding(Padding::new(
-                centered_area.width / 2 - spacer,
-                0,
-                centered_area.height / 2 - spacer + 1,
-                0,
-            )));
+        let breadcrumbs = Paragraph::new(state.browser.breadcrumbs())
+            .style(theme.foreground)
+            .block(Block::default().borders(Borders::BOTTOM));
+        frame.render_widget(breadcrumbs, top);
 
+        let list = view_utility::create_browse_list(&state.browser, theme);
+        let mut list_state = state.browser.list_state.clone();
+        frame.render_stateful_widget(list, bottom, &mut list_state);
+    } else if state.is_viewing_queue {
+        // Queue Section
         frame.render_widget(Clear, frame.area());
-        frame.render_widget(volume_paragraph, centered_area);
+        let title = match state.queue.cursor {
+            Some(cursor) => format!("QUEUE {}/{}", cursor + 1, state.queue.tracks.len()),
+            None => format!("QUEUE {}", state.queue.tracks.len()),
+        };
+        let list = view_utility::create_queue_list(state)
+            .block(Block::default().borders(Borders::BOTTOM).title(title))
+            .style(theme.foreground);
+        let mut list_state = state.queue_state.clone();
+        frame.render_stateful_widget(list, frame.area(), &mut list_state);
+    } else if state.is_viewing_history {
+        // History Section
+        frame.render_widget(Clear, frame.area());
+        let list = view_utility::create_history_list(state)
+            .block(
+                Block::default()
+                    .borders(Borders::BOTTOM)
+                    .title("RECENTLY PLAYED"),
+            )
+            .style(theme.foreground);
+        let mut list_state = state.history_state.clone();
+        frame.render_stateful_widget(list, frame.area(), &mut list_state);
+    } else if state.is_viewing_playlists {
+        // Playlist Section
+        frame.render_widget(Clear, frame.area());
+        let mut area = frame.area();
+        if state.is_naming_playlist {
+            let [top, bottom] =
+                Layout::vertical([Constraint::Length(5), Constraint::Fill(1)]).areas(area);
+            Paragraph::new(state.playlist_name.as_str())
+                .block(
+                    Block::bordered()
+                        .style(theme.input)
+                        .border_type(BorderType::Rounded)
+                        .padding(Padding::uniform(1))
+                        .title("NAME"),
+                )
+                .render(top, frame.buffer_mut());
+            area = bottom;
+        }
+
+        let (list, title) = match state.opened_playlist {
+            Some(list) => (
+                view_utility::create_playlist_entries(state, list),
+                format!("PLAYLIST {}", state.playlists.lists[list].name),
+            ),
+            None => (
+                view_utility::create_playlist_list(state),
+                String::from("PLAYLISTS"),
+            ),
+        };
+        let list = list
+            .block(
+                Block::default()
+                    .borders(Borders::BOTTOM)
+                    .title(title)
+                    .title_bottom(state.playlist_notice.as_str()),
+            )
+            .style(theme.foreground);
+        let mut list_state = state.playlist_state.clone();
+        frame.render_stateful_widget(list, area, &mut list_state);
     } else if state.is_searching {
         // Search Section
         frame.render_widget(Clear, frame.area());
+        let [top, bottom] =
+            Layout::vertical([Constraint::Length(5), Constraint::Fill(1)]).areas(frame.area());
         Paragraph::new(state.keyword.as_str())
             .block(
                 Block::bordered()
-                    .fg(Color::Green)
+                    .style(theme.input)
                     .border_type(BorderType::Rounded)
                     .padding(Padding::uniform(1))
                     .title("SEARCH"),
             )
-            .render(frame.area(), frame.buffer_mut());
+            .render(top, frame.buffer_mut());
+
+        let mut search_state = state.search_state.clone();
+        if !state.is_picking_result {
+            search_state.select(None);
+        }
+        let table = view_utility::create_table(
+            &state.tracks,
+            &state.matched_tracks,
+            &state.stats,
+            theme,
+            search_state.selected(),
+            bottom.width,
+            marquee::step(state.marquee_clock),
+        );
+        frame.render_stateful_widget(table, bottom, &mut search_state);
     } else {
         // Main Screens
-        if state.is_choosing {
-            // List seciton
-            let left_top_block = Block::default().borders(Borders::NONE).fg(Color::Yellow);
-            frame.render_widget(left_top_block, frame.area());
+        // Wide screens keep the library on the left, next to whatever else is showing.
+        let mut area = frame.area();
+        if profile == Profile::Wide {
+            let [left, right] =
+                Layout::horizontal([Constraint::Percentage(45), Constraint::Fill(1)]).areas(area);
+            render_library(frame, left, state);
+            area = right;
+        }
 
-            let table = view_utility::create_table(&state.tracks);
-            let mut table_state = state.table_state.clone();
-            frame.render_stateful_widget(table, frame.area(), &mut table_state);
+        if state.is_choosing && profile != Profile::Wide {
+            // List seciton
+            render_library(frame, area, state);
+        } else if state.is_viewing_lyrics {
+            // Lyrics Section
+            render_lyrics(frame, area, state, sink);
         } else {
             // Player Section
-            let [top, bottom] = Layout::vertical([Constraint::Length(6), Constraint::Fill(1)])
-                .vertical_margin(1)
-                .flex(ratatui::layout::Flex::Center)
-                .areas(frame.area());
-            let player_color = match sink.is_playing {
-                true => Color::Yellow,
-                false => Color::Magenta,
-            };
-
-            let mut index = 1; // Point at something on startup.
-            if let Some(current_index) = state.current_track_index {
-                index = current_index;
-            }
-            if let Some(music) = state.tracks.get(index) {
-                let progress_label = format!(" {}/{}", sink.position.as_secs(), music.length);
-                let progress_block = view_utility::title_block(&player_color, &progress_label);
-                view_utility::render_progress(
-                    &sink.position,
-                    top,
-                    frame.buffer_mut(),
-                    progress_block,
-                    music.length as f64,
-                );
-
-                let name = Line::from(vec![Span::styled(
-                    &music.name,
-                    Style::default().fg(player_color),
-                )])
-                .right_aligned();
-                let author = Line::from(vec![Span::styled(
-                    &music.author,
-                    Style::default().fg(Color::Green),
-                )])
-                .right_aligned();
-
-                let info_para = Paragraph::new(name)
-                    .wrap(ratatui::widgets::Wrap { trim: true })
-                    .alignment(ratatui::layout::Alignment::Left)
-                    .block(
-                        Block::default()
-                            .borders(Borders::all())
-                            .padding(Padding::top(2))
-                            .style(Style::default().fg(player_color))
-                            .title_bottom(author),
-                    );
-                frame.render_widget(info_para, bottom);
-            }
+            render_player(frame, area, state, sink, profile);
         }
     }
 }
+
+fn render_library(frame: &mut Frame, area: Rect, state: &PlayerState) {
+    let theme = &state.theme;
+    let left_top_block = Block::default()
+        .borders(Borders::NONE)
+        .style(theme.foreground);
+    frame.render_widget(left_top_block, area);
+
+    let table = view_utility::create_table(
+        &state.tracks,
+        &state.library,
+        &state.stats,
+        theme,
+        state.table_state.selected(),
+        area.width,
+        marquee::step(state.marquee_clock),
+    );
+    let mut table_state = state.table_state.clone();
+    frame.render_stateful_widget(table, area, &mut table_state);
+}
+
+fn render_lyrics(frame: &mut Frame, area: Rect, state: &PlayerState, sink: &SinkState) {
+    let theme = &state.theme;
+    frame.render_widget(Clear, area);
+    let track = state
+        .current_track_index
+        .and_then(|index| state.tracks.get(index));
+    let title = track.map(|track| track.name.as_str()).unwrap_or_default();
+    let block = Block::default()
+        .borders(Borders::BOTTOM)
+        .title(format!("LYRICS {title}"))
+        .style(theme.foreground);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let mut lyrics = state.lyrics.borrow_mut();
+    if let Some(track) = track {
+        lyrics.request(&track.path);
+    }
+    match track.and_then(|track| lyrics.get(&track.path)) {
+        Some(lyrics) => frame.render_widget(
+            view_utility::create_lyrics(
+                lyrics,
+                sink.position,
+                state.lyrics_scroll,
+                inner.height,
+                theme,
+            ),
+            inner,
+        ),
+        // Nothing until they're read.
+        None if lyrics.is_loading() => {}
+        None => frame.render_widget(
+            Paragraph::new("No lyrics").centered().style(theme.muted),
+            view_utility::center(inner, Constraint::Fill(1), Constraint::Length(1)),
+        ),
+    }
+}
+
+// Compact screens drop the cover, the visualizer and the spare lines around the text.
+fn render_player(
+    frame: &mut Frame,
+    area: Rect,
+    state: &PlayerState,
+    sink: &SinkState,
+    profile: Profile,
+) {
+    let theme = &state.theme;
+    let compact = profile == Profile::Compact;
+    let (progress_height, margin, info_padding) = match compact {
+        true => (3, 0, 0),
+        false => (6, 1, 2),
+    };
+    let [top, bottom] =
+        Layout::vertical([Constraint::Length(progress_height), Constraint::Fill(1)])
+            .vertical_margin(margin)
+            .flex(ratatui::layout::Flex::Center)
+            .areas(area);
+    let player_style = match sink.is_playing {
+        true => theme.foreground,
+        false => theme.paused,
+    };
+
+    let mut index = 1; // Point at something on startup.
+    if let Some(current_index) = state.current_track_index {
+        index = current_index;
+    }
+    let Some(music) = state.tracks.get(index) else {
+        return;
+    };
+    let elapsed = sink.position.as_secs().min(music.length);
+    let progress_label = match state.show_remaining {
+        true => format!(
+            " -{}/{}",
+            format_time(music.length - elapsed),
+            format_time(music.length)
+        ),
+        false => format!(" {}/{}", format_time(elapsed), format_time(music.length)),
+    };
+    let mut progress_block = view_utility::title_block(player_style, &progress_label, theme);
+    if compact {
+        progress_block = progress_block.padding(Padding::new(0, 1, 1, 0));
+    }
+    view_utility::render_progress(
+        &sink.position,
+        top,
+        frame.buffer_mut(),
+        progress_block,
+        music.length as f64,
+        state.waveforms.get(music),
+        theme,
+    );
+
+    let repeat = match state.repeat {
+        Repeat::Off => String::new(),
+        repeat => format!(" {repeat} "),
+    };
+
+    // Where the track is in the queue and how long until everything after it ends.
+    let left = music.length - elapsed + time_after(index, state);
+    let position = match state.queue.current() == Some(index) {
+        true => format!(
+            "{}/{} ",
+            state.queue.upcoming_start() + 1,
+            state.queue.tracks.len()
+        ),
+        false => String::new(),
+    };
+    let queue_label = Line::from(format!(" {position}{} left ", format_time(left))).right_aligned();
+
+    // Square, as two pixels go into every cell.
+    let mut info_area = bottom;
+    if theme.covers && !compact && bottom.height >= 4 && bottom.width >= 30 {
+        let width = (bottom.height * 2).min(bottom.width / 2);
+        let mut covers = state.covers.borrow_mut();
+        if let Some(art) = covers.get(music, width, bottom.height) {
+            let [cover_area, rest] =
+                Layout::horizontal([Constraint::Length(art.width), Constraint::Fill(1)])
+                    .areas(bottom);
+            frame.render_widget(art, cover_area);
+            info_area = rest;
+        }
+    }
+
+    // The visualizer goes under the title when there's room for both.
+    if state.visualizer != Visualizer::Off && !compact && info_area.height >= 8 {
+        let [rest, visualizer_area] =
+            Layout::vertical([Constraint::Length(5), Constraint::Fill(1)]).areas(info_area);
+        view_utility::render_visualizer(
+            state.visualizer,
+            &sink.spectrum,
+            visualizer_area,
+            frame.buffer_mut(),
+            theme,
+        );
+        info_area = rest;
+    }
+
+    // Inside the borders, too long a title or artist scrolls by.
+    let text_width = info_area.width.saturating_sub(2) as usize;
+    let step = marquee::step(state.marquee_clock);
+    let name = Line::from(vec![Span::styled(
+        marquee::scroll(&music.name, text_width, step),
+        player_style,
+    )])
+    .right_aligned();
+    let author = Line::from(vec![Span::styled(
+        marquee::scroll(&music.author, text_width, step),
+        theme.accent,
+    )])
+    .right_aligned();
+
+    let info_para = Paragraph::new(name)
+        .alignment(ratatui::layout::Alignment::Left)
+        .block(
+            Block::default()
+                .borders(Borders::all())
+                .padding(Padding::top(info_padding))
+                .style(player_style)
+                .title(repeat)
+                .title(queue_label)
+                .title_bottom(author),
+        );
+    frame.render_widget(info_para, info_area);
+}
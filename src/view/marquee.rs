@@ -0,0 +1,56 @@
+use std::time::Instant;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// Milliseconds per column scrolled.
+const STEP_MILLIS: u128 = 180;
+// Steps the text holds still at the start of every round, so it can be read.
+const PAUSE: usize = 8;
+const GAP: &str = "   ";
+
+// How many steps the marquee has taken since `clock` started.
+pub(crate) fn step(clock: Instant) -> usize {
+    (clock.elapsed().as_millis() / STEP_MILLIS) as usize
+}
+
+// Columns on screen, CJK characters take two and so do most emoji, including the ones joined
+// from several code points.
+pub(crate) fn display_width(text: &str) -> usize {
+    text.graphemes(true).map(UnicodeWidthStr::width).sum()
+}
+
+// The `width` columns of `text` showing at `step`. Text that fits is left alone, longer text
+// runs past and comes around again after a gap. A wide character cut by either edge is
+// replaced with spaces, so the result is never wider than asked.
+pub(crate) fn scroll(text: &str, width: usize, step: usize) -> String {
+    let text_width = display_width(text);
+    if text_width <= width {
+        return text.to_string();
+    }
+    let round = text_width + GAP.len();
+    let shift = (step % (round + PAUSE)).saturating_sub(PAUSE);
+
+    let mut visible = String::new();
+    let mut column = 0;
+    let looped = format!("{text}{GAP}{text}");
+    for grapheme in looped.graphemes(true) {
+        let (start, end) = (column, column + grapheme.width());
+        column = end;
+        if end <= shift {
+            continue;
+        }
+        let (left, right) = (shift, shift + width);
+        if start >= right {
+            break;
+        }
+        match start >= left && end <= right {
+            true => visible.push_str(grapheme),
+            false => visible.push_str(&" ".repeat(end.min(right) - start.max(left))),
+        }
+    }
+    visible
+}
+
+#[cfg(test)]
+mod test;
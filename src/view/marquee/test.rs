@@ -0,0 +1,32 @@
+use super::*;
+
+#[test]
+fn test_display_width() {
+    assert_eq!(display_width("abc"), 3);
+    assert_eq!(display_width("東京"), 4);
+    assert_eq!(display_width("👨‍👩‍👧"), 2);
+    assert_eq!(display_width("e\u{301}"), 1);
+}
+
+#[test]
+fn test_scroll() {
+    assert_eq!(scroll("short", 10, 100), "short");
+    // Holds still at first, then moves a column per step.
+    assert_eq!(scroll("abcdef", 4, 0), "abcd");
+    assert_eq!(scroll("abcdef", 4, PAUSE + 1), "bcde");
+    assert_eq!(scroll("abcdef", 4, PAUSE + 4), "ef  ");
+    assert_eq!(scroll("abcdef", 4, PAUSE + 7), "  ab");
+    // And starts over after a round.
+    assert_eq!(scroll("abcdef", 4, PAUSE + 9), "abcd");
+}
+
+#[test]
+fn test_scroll_wide_characters() {
+    // 東 is cut by the left edge, then ワ by the right.
+    let text = "東京タワー";
+    assert_eq!(scroll(text, 5, PAUSE + 1), " 京タ");
+    assert_eq!(scroll(text, 5, PAUSE + 2), "京タ ");
+    for step in 0..40 {
+        assert_eq!(display_width(&scroll(text, 5, step)), 5);
+    }
+}
@@ -0,0 +1,24 @@
+use ratatui::layout::Rect;
+
+// How much goes on screen, picked from the terminal size on every frame. The HAT display
+// only fits the essentials, 480x320 panels get the usual screens and HDMI monitors show
+// the library and the player side by side.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Profile {
+    Compact,
+    Standard,
+    Wide,
+}
+
+impl Profile {
+    pub fn for_area(area: Rect) -> Self {
+        match (area.width, area.height) {
+            (width, height) if width < 40 || height < 14 => Profile::Compact,
+            (width, height) if width >= 100 && height >= 24 => Profile::Wide,
+            _ => Profile::Standard,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
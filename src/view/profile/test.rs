@@ -0,0 +1,20 @@
+use super::*;
+
+#[test]
+fn test_for_area() {
+    assert_eq!(Profile::for_area(Rect::new(0, 0, 30, 15)), Profile::Compact);
+    assert_eq!(
+        Profile::for_area(Rect::new(0, 0, 120, 10)),
+        Profile::Compact
+    );
+    assert_eq!(
+        Profile::for_area(Rect::new(0, 0, 60, 20)),
+        Profile::Standard
+    );
+    // Tall but narrow stays standard, there's no room for two columns.
+    assert_eq!(
+        Profile::for_area(Rect::new(0, 0, 80, 60)),
+        Profile::Standard
+    );
+    assert_eq!(Profile::for_area(Rect::new(0, 0, 240, 67)), Profile::Wide);
+}
@@ -4,6 +4,14 @@ use ratatui::{
 };
 
 use super::*;
+use crate::browse::{BrowseRoot, Browser};
+use crate::lyrics::Lyrics;
+use crate::playlist::Origin;
+use crate::spectrum::Spectrum;
+use crate::stats::{Stats, TrackStats, now};
+use crate::theme::Theme;
+use ratatui::widgets::Sparkline;
+use ratatui::widgets::canvas::{self, Canvas};
 
 pub(crate) fn render_progress(
     progress: &Duration,
@@ -11,6 +19,8 @@ pub(crate) fn render_progress(
     buf: &mut Buffer,
     title: Block,
     duration: f64,
+    waveform: Option<&[u8]>,
+    theme: &Theme,
 ) {
     let progress = progress.as_secs_f64();
     let ratio = ((progress / duration) * 100.0).round() / 100.0;
@@ -18,20 +28,53 @@ pub(crate) fn render_progress(
         return;
     }
 
+    // The plain gauge stands in until the track's waveform is measured.
+    if let Some(peaks) = waveform {
+        let inner = title.inner(area);
+        title.render(area, buf);
+        render_waveform(peaks, ratio, inner, buf, theme);
+        return;
+    }
+
     LineGauge::default()
         .block(title)
-        .filled_style(Color::Green)
+        .filled_style(theme.progress)
         .ratio(ratio)
         .label("")
         .line_set(symbols::line::THICK)
         .render(area, buf);
 }
 
-pub(crate) fn title_block<'a>(color: &'a Color, progress: &'a str) -> Block<'a> {
-    let progress = Line::from(vec![Span::styled(
-        progress,
-        Style::default().fg(Color::Green),
-    )]);
+// Bars rise from the bottom in eighths of a cell, green up to where the track is.
+fn render_waveform(peaks: &[u8], ratio: f64, area: Rect, buf: &mut Buffer, theme: &Theme) {
+    const LEVELS: [&str; 9] = [" ", "▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+    if area.is_empty() || peaks.is_empty() {
+        return;
+    }
+    let width = area.width as usize;
+    let played = (ratio * width as f64).round() as usize;
+    for column in 0..width {
+        let start = column * peaks.len() / width;
+        let end = ((column + 1) * peaks.len() / width).clamp(start + 1, peaks.len());
+        let peak = peaks[start..end].iter().copied().max().unwrap_or(0) as usize;
+        // Silence still gets the lowest bar, so the length of the track shows.
+        let eighths = (peak * area.height as usize * 8).div_ceil(255).max(1);
+        let style = match column < played {
+            true => theme.progress,
+            false => theme.remaining,
+        };
+        for row in 0..area.height {
+            let below = (area.height - 1 - row) as usize * 8;
+            let level = eighths.saturating_sub(below).min(8);
+            buf[(area.x + column as u16, area.y + row)]
+                .set_symbol(LEVELS[level])
+                .set_style(style);
+        }
+    }
+}
+
+pub(crate) fn title_block<'a>(style: Style, progress: &'a str, theme: &Theme) -> Block<'a> {
+    let progress = Line::from(vec![Span::styled(progress, theme.accent)]);
 
     Block::new()
         .borders(Borders::BOTTOM)
@@ -43,34 +86,58 @@ pub(crate) fn title_block<'a>(color: &'a Color, progress: &'a str) -> Block<'a>
         })
         .title_bottom(progress)
         .title_alignment(ratatui::layout::Alignment::Right)
-        .fg(*color)
-        .bg(Color::DarkGray)
+        .style(style.patch(theme.bar))
 }
 
-pub(crate) fn create_table(tracks: &[Audio]) -> Table<'_> {
-    let header = Row::new(["Song", "Artist"])
+// The selected row scrolls its title and artist when they don't fit `width`.
+pub(crate) fn create_table<'a>(
+    tracks: &'a [Audio],
+    library: &[usize],
+    stats: &Stats,
+    theme: &Theme,
+    selected: Option<usize>,
+    width: u16,
+    step: usize,
+) -> Table<'a> {
+    let header = Row::new(["Song", "Artist", "Rating"])
         .style(Style::new().bold())
         .bottom_margin(1);
 
+    let widths = [
+        Constraint::Percentage(50),
+        Constraint::Fill(1),
+        Constraint::Length(7),
+    ];
+    // Less the highlight symbol.
+    let [song_width, artist_width, _] = Layout::horizontal(widths)
+        .spacing(1)
+        .areas(Rect::new(0, 0, width.saturating_sub(1), 1))
+        .map(|column| column.width as usize);
+
     //TODO: Refactor.
-    let rows: Vec<Row> = tracks
+    let rows: Vec<Row> = library
         .iter()
-        .map(|item| {
+        .enumerate()
+        .map(|(row, index)| {
+            let item = &tracks[*index];
             let style = match item.is_playing {
-                true => Style::default()
-                    .fg(CUSTOM_LABEL_COLOR)
-                    .add_modifier(Modifier::BOLD),
+                true => theme.playing,
                 _ => Style::default(),
             };
+            let (name, author) = match Some(row) == selected {
+                true => (
+                    marquee::scroll(&item.name, song_width, step),
+                    marquee::scroll(&item.author, artist_width, step),
+                ),
+                false => (item.name.clone(), item.author.clone()),
+            };
 
-            Row::new([item.name.clone(), item.author.clone()]).style(style)
+            Row::new([name, author, rating_label(&stats.get(&item.path))]).style(style)
         })
         .collect();
 
     //let footer = Row::new(["Lemon", "Lemon Tree", "000"]);
 
-    let widths = [Constraint::Percentage(50), Constraint::Fill(1)];
-
     Table::new(rows, widths)
         //.footer(footer.italic())
         //.style(Color::White)
@@ -79,7 +146,7 @@ pub(crate) fn create_table(tracks: &[Audio]) -> Table<'_> {
         //.cell_highlight_style(Style::new().reversed().yellow())
         .header(header)
         .column_spacing(1)
-        .row_highlight_style(Style::new().fg(Color::Green))
+        .row_highlight_style(theme.accent)
         .highlight_symbol(">")
 }
 
@@ -90,6 +157,230 @@ pub(crate) fn create_list(rows: Vec<Line>, highlight: Style) -> List {
         .repeat_highlight_symbol(true)
 }
 
+pub(crate) fn create_browse_list<'a>(browser: &'a Browser, theme: &Theme) -> List<'a> {
+    let actions = browser.actions().into_iter().map(|action| {
+        Line::from(Span::styled(
+            format!("▶ {}", browser.action_label(action)),
+            theme.playing,
+        ))
+    });
+
+    let entries = browser.entries.iter().map(|entry| {
+        let label = match entry.is_track {
+            true => entry.label.clone(),
+            false if browser.root == BrowseRoot::Folders => format!("{}/", entry.label),
+            false => entry.label.clone(),
+        };
+        Line::from(vec![
+            Span::from(label),
+            Span::styled(format!("  {}", entry.detail), theme.muted),
+        ])
+    });
+
+    List::new(actions.chain(entries).collect::<Vec<Line>>())
+        .highlight_style(theme.accent)
+        .highlight_symbol(">")
+}
+
+pub(crate) fn create_queue_list(state: &PlayerState) -> List<'_> {
+    let theme = &state.theme;
+    let start = state.queue.upcoming_start();
+    let rows: Vec<Line> = state.queue.tracks[start.min(state.queue.tracks.len())..]
+        .iter()
+        .enumerate()
+        .map(|(row, index)| {
+            let track = &state.tracks[*index];
+            let style = match state.queue.cursor.is_some() && row == 0 {
+                true => theme.playing,
+                false => Style::default(),
+            };
+            Line::from(vec![
+                Span::styled(track.name.as_str(), style),
+                Span::styled(format!("  {}", track.author), theme.muted),
+            ])
+        })
+        .collect();
+
+    let highlight = match state.is_moving_entry {
+        true => Style::new().reversed(),
+        false => theme.accent,
+    };
+    List::new(rows)
+        .highlight_style(highlight)
+        .highlight_symbol(">")
+}
+
+pub(crate) fn create_history_list(state: &PlayerState) -> List<'_> {
+    let theme = &state.theme;
+    let now = now();
+    let rows: Vec<Line> = state
+        .recently_played
+        .iter()
+        .map(|(index, time)| {
+            let track = &state.tracks[*index];
+            Line::from(vec![
+                Span::from(track.name.as_str()),
+                Span::styled(
+                    format!("  {}  {}", track.author, time_ago(now, *time)),
+                    theme.muted,
+                ),
+            ])
+        })
+        .collect();
+
+    List::new(rows)
+        .highlight_style(theme.accent)
+        .highlight_symbol(">")
+}
+
+pub(crate) fn create_playlist_list(state: &PlayerState) -> List<'_> {
+    let theme = &state.theme;
+    let new = Line::from(Span::styled("+ New playlist", theme.playing));
+    let lists = state
+        .playlists
+        .lists
+        .iter()
+        .enumerate()
+        .map(|(list, playlist)| {
+            // Tracks get added to the target one.
+            let style = match state.playlists.target == Some(list) {
+                true => theme.accent,
+                false => Style::default(),
+            };
+            let source = match &playlist.origin {
+                Origin::Own => String::new(),
+                Origin::File(file) => format!("  from {}", file.display()),
+                Origin::Smart => String::from("  smart"),
+            };
+            Line::from(vec![
+                Span::styled(playlist.name.as_str(), style),
+                Span::styled(
+                    format!("  {} tracks{source}", playlist.tracks.len()),
+                    theme.muted,
+                ),
+            ])
+        });
+
+    List::new(std::iter::once(new).chain(lists).collect::<Vec<Line>>())
+        .highlight_style(theme.accent)
+        .highlight_symbol(">")
+}
+
+pub(crate) fn create_playlist_entries(state: &PlayerState, list: usize) -> List<'_> {
+    let theme = &state.theme;
+    let rows: Vec<Line> = state
+        .playlists
+        .resolve(list, &state.tracks)
+        .into_iter()
+        .zip(&state.playlists.lists[list].tracks)
+        .map(|(index, path)| match index {
+            Some(index) => {
+                let track = &state.tracks[index];
+                Line::from(vec![
+                    Span::from(track.name.as_str()),
+                    Span::styled(format!("  {}", track.author), theme.muted),
+                ])
+            }
+            None => Line::from(Span::styled(
+                format!("{} (missing)", path.display()),
+                theme.muted,
+            )),
+        })
+        .collect();
+
+    List::new(rows)
+        .highlight_style(theme.accent)
+        .highlight_symbol(">")
+}
+
+// Synced lyrics keep the current line in the middle of `height`, unsynced ones start at
+// `scroll`.
+pub(crate) fn create_lyrics<'a>(
+    lyrics: &'a Lyrics,
+    position: Duration,
+    scroll: usize,
+    height: u16,
+    theme: &Theme,
+) -> Paragraph<'a> {
+    let current = lyrics.current_line(position);
+    let top = match current {
+        Some(line) => line.saturating_sub(height as usize / 2),
+        None if matches!(lyrics, Lyrics::Synced(_)) => 0,
+        None => scroll,
+    };
+    let lines: Vec<Line> = lyrics
+        .lines()
+        .into_iter()
+        .enumerate()
+        .skip(top)
+        .map(|(line, text)| match Some(line) == current {
+            true => Line::from(text).style(theme.foreground.bold()),
+            false => Line::from(text).style(theme.muted),
+        })
+        .collect();
+    Paragraph::new(lines).centered()
+}
+
+// Bars are stretched over the width, the waveform is drawn in braille.
+pub(crate) fn render_visualizer(
+    visualizer: Visualizer,
+    spectrum: &Spectrum,
+    area: Rect,
+    buf: &mut Buffer,
+    theme: &Theme,
+) {
+    match visualizer {
+        Visualizer::Off => {}
+        Visualizer::Bars => {
+            if spectrum.bands.is_empty() {
+                return;
+            }
+            let data: Vec<u64> = (0..area.width as usize)
+                .map(|column| {
+                    let band = column * spectrum.bands.len() / area.width as usize;
+                    (spectrum.bands[band] * 100.0) as u64
+                })
+                .collect();
+            Sparkline::default()
+                .data(&data)
+                .max(100)
+                .style(theme.progress)
+                .render(area, buf);
+        }
+        Visualizer::Scope => {
+            let points = &spectrum.scope;
+            // Canvas lines only take a colour.
+            let color = theme.progress.fg.unwrap_or(Color::Reset);
+            Canvas::default()
+                .marker(symbols::Marker::Braille)
+                .x_bounds([0.0, points.len().max(1) as f64])
+                .y_bounds([-1.0, 1.0])
+                .paint(|context| {
+                    for (x, pair) in points.windows(2).enumerate() {
+                        context.draw(&canvas::Line::new(
+                            x as f64,
+                            pair[0] as f64,
+                            x as f64 + 1.0,
+                            pair[1] as f64,
+                            color,
+                        ));
+                    }
+                })
+                .render(area, buf);
+        }
+    }
+}
+
+fn time_ago(now: u64, time: u64) -> String {
+    let seconds = now.saturating_sub(time);
+    match seconds {
+        0..60 => String::from("just now"),
+        60..3600 => format!("{}m ago", seconds / 60),
+        3600..86400 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86400),
+    }
+}
+
 pub(crate) fn center(area: Rect, horizontal: Constraint, vertical: Constraint) -> Rect {
     let [area] = Layout::horizontal([horizontal])
         .flex(Flex::Center)
@@ -97,3 +388,11 @@ pub(crate) fn center(area: Rect, horizontal: Constraint, vertical: Constraint) -
     let [area] = Layout::vertical([vertical]).flex(Flex::Center).areas(area);
     area
 }
+
+fn rating_label(track: &TrackStats) -> String {
+    let stars = "★".repeat(track.rating.unwrap_or(0) as usize);
+    match track.favourite {
+        true => format!("{stars}♥"),
+        false => stars,
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use rodio::{Decoder, Source};
+
+use crate::Audio;
+
+pub(crate) const FILE_NAME: &str = "waveforms.tsv";
+// Envelopes are kept at this many points and squeezed into the width of the screen.
+pub(crate) const PEAKS: usize = 256;
+// Blocks of this many per second are measured while decoding, before knowing the length.
+const BLOCKS_PER_SECOND: u32 = 20;
+// The file is written anew once this many of its lines were replaced by newer ones.
+const STALE_LIMIT: usize = 50;
+
+// A track to measure and the modification time it had.
+type Request = (PathBuf, u64);
+type Measured = (PathBuf, u64, Option<Vec<u8>>);
+
+// The loudness over the length of every track, measured by a background thread and kept in
+// waveforms.tsv next to stats.toml. An envelope is measured again once its file changed.
+// New envelopes are appended, and the lines they replace are dropped when the file is rewritten.
+#[derive(Default)]
+pub(crate) struct Waveforms {
+    path: Option<PathBuf>,
+    // With the modification time they were measured at.
+    envelopes: HashMap<PathBuf, (u64, Vec<u8>)>,
+    // Lines in the file that a later one replaced.
+    stale: usize,
+    pending: HashSet<PathBuf>,
+    worker: Option<(Sender<Request>, Receiver<Measured>)>,
+}
+
+impl Waveforms {
+    pub fn load(path: PathBuf) -> Self {
+        let (envelopes, lines) = match fs::read_to_string(&path) {
+            Ok(file) => {
+                let envelopes: HashMap<_, _> = file
+                    .lines()
+                    .filter_map(from_line)
+                    .map(|(path, added, peaks)| (path, (added, peaks)))
+                    .collect();
+                (envelopes, file.lines().count())
+            }
+            Err(_) => (HashMap::new(), 0),
+        };
+        let mut waveforms = Waveforms {
+            path: Some(path),
+            stale: lines.saturating_sub(envelopes.len()),
+            envelopes,
+            ..Default::default()
+        };
+        if waveforms.stale > 0 {
+            waveforms.rewrite();
+        }
+        waveforms
+    }
+
+    pub fn get(&self, track: &Audio) -> Option<&[u8]> {
+        match self.envelopes.get(&track.path) {
+            Some((added, peaks)) if *added == track.added => Some(peaks),
+            _ => None,
+        }
+    }
+
+    // Queues the track for measuring, unless it's known or already on its way.
+    pub fn request(&mut self, track: &Audio) {
+        if self.get(track).is_some() || !self.pending.insert(track.path.clone()) {
+            return;
+        }
+        let (tx, _) = self.worker.get_or_insert_with(spawn_worker);
+        tx.send((track.path.clone(), track.added)).unwrap_or(());
+    }
+
+    // Picks up whatever the background thread finished, called from the main loop.
+    pub fn receive(&mut self) {
+        let Some((_, rx)) = &self.worker else {
+            return;
+        };
+        let measured: Vec<Measured> = rx.try_iter().collect();
+        for (path, added, peaks) in measured {
+            // Files that can't be decoded stay pending, so they aren't tried again.
+            let Some(peaks) = peaks else {
+                continue;
+            };
+            self.pending.remove(&path);
+            self.append(&path, added, &peaks);
+            if self.envelopes.insert(path, (added, peaks)).is_some() {
+                self.stale += 1;
+            }
+        }
+        if self.stale >= STALE_LIMIT {
+            self.rewrite();
+        }
+    }
+
+    fn append(&self, track: &Path, added: u64, peaks: &[u8]) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        // Kept in memory for this run, but a lossy path wouldn't match the track next time.
+        let Some(line) = to_line(track, added, peaks) else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{line}"));
+        if written.is_err() {
+            log::warn!("Can't write the waveforms: {}", path.display());
+        }
+    }
+
+    // One line per envelope, leaving out the ones replaced since and anything unreadable.
+    fn rewrite(&mut self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let mut file = String::new();
+        for (track, (added, peaks)) in &self.envelopes {
+            if let Some(line) = to_line(track, *added, peaks) {
+                file.push_str(&line);
+                file.push('\n');
+            }
+        }
+        match fs::write(path, file) {
+            Ok(()) => self.stale = 0,
+            Err(_) => log::warn!("Can't write the waveforms: {}", path.display()),
+        }
+    }
+}
+
+fn spawn_worker() -> (Sender<Request>, Receiver<Measured>) {
+    let (request_tx, request_rx) = mpsc::channel::<Request>();
+    let (measured_tx, measured_rx) = mpsc::channel::<Measured>();
+    let _ = thread::Builder::new()
+        .name("waveform".to_string())
+        .spawn(move || {
+            for (path, added) in request_rx {
+                let peaks = measure(&path);
+                if measured_tx.send((path, added, peaks)).is_err() {
+                    break;
+                }
+            }
+        });
+    (request_tx, measured_rx)
+}
+
+// Decodes the whole file, keeping only the loudest sample of every block.
+fn measure(path: &Path) -> Option<Vec<u8>> {
+    let decoder = Decoder::new(BufReader::new(File::open(path).ok()?)).ok()?;
+    let block = (decoder.sample_rate() * decoder.channels() as u32 / BLOCKS_PER_SECOND).max(1);
+    let mut blocks = Vec::new();
+    let mut peak = 0u16;
+    for (i, sample) in decoder.enumerate() {
+        peak = peak.max(sample.unsigned_abs());
+        if (i as u32 + 1).is_multiple_of(block) {
+            blocks.push(peak);
+            peak = 0;
+        }
+    }
+    blocks.push(peak);
+    Some(envelope(&blocks, PEAKS))
+}
+
+// The loudest block of every stretch, scaled so the loudest of the track is 255.
+pub(crate) fn envelope(blocks: &[u16], count: usize) -> Vec<u8> {
+    let loudest = blocks.iter().copied().max().unwrap_or(0).max(1) as u32;
+    (0..count)
+        .map(|point| {
+            let start = point * blocks.len() / count;
+            let end = ((point + 1) * blocks.len() / count).max(start + 1);
+            let peak = blocks[start.min(blocks.len().saturating_sub(1))..end.min(blocks.len())]
+                .iter()
+                .copied()
+                .max()
+                .unwrap_or(0);
+            (peak as u32 * 255 / loudest) as u8
+        })
+        .collect()
+}
+
+// <modification time> <peaks in hex> <path>, the path goes last as it may hold tabs.
+// None for paths that aren't valid UTF-8.
+fn to_line(path: &Path, added: u64, peaks: &[u8]) -> Option<String> {
+    let path = path.to_str()?;
+    let hex: String = peaks.iter().map(|peak| format!("{peak:02x}")).collect();
+    Some(format!("{added}\t{hex}\t{path}"))
+}
+
+fn from_line(line: &str) -> Option<(PathBuf, u64, Vec<u8>)> {
+    let mut fields = line.splitn(3, '\t');
+    let added = fields.next()?.parse().ok()?;
+    let hex = fields.next()?;
+    let peaks = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    Some((PathBuf::from(fields.next()?), added, peaks))
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,32 @@
+use super::*;
+
+#[test]
+fn test_envelope() {
+    let blocks = [0, 100, 50, 200, 400, 0, 0, 20];
+    assert_eq!(envelope(&blocks, 4), vec![63, 127, 255, 12]);
+    // Fewer blocks than points repeat them.
+    assert_eq!(envelope(&[10, 20], 4), vec![127, 127, 255, 255]);
+    assert_eq!(envelope(&[], 2), vec![0, 0]);
+}
+
+#[test]
+fn test_line_round_trip() {
+    let path = Path::new("/music/a\tb.flac");
+    let line = to_line(path, 1_700_000_000, &[0, 15, 255]).unwrap();
+    assert_eq!(line, "1700000000\t000fff\t/music/a\tb.flac");
+    assert_eq!(
+        from_line(&line),
+        Some((path.to_path_buf(), 1_700_000_000, vec![0, 15, 255]))
+    );
+    assert_eq!(from_line("12\t0g\t/music/c.mp3"), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_non_utf8_paths_are_skipped() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = Path::new(OsStr::from_bytes(b"/music/caf\xe9.flac"));
+    assert_eq!(to_line(path, 1, &[0]), None);
+}